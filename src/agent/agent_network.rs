@@ -4,6 +4,7 @@ use crate::model::poker_network::PokerNetwork;
 use candle_core::Tensor;
 
 use rand::distributions::Distribution;
+use rand::rngs::StdRng;
 
 pub struct AgentNetwork {
     network: PokerNetwork,
@@ -18,7 +19,40 @@ impl Agent for AgentNetwork {
         action_config: &crate::game::action::ActionConfig,
         device: &candle_core::Device,
         no_invalid: bool,
+        rng: &mut StdRng,
     ) -> Result<usize, Box<dyn std::error::Error>> {
+        let proba_tensor = self.get_proba_tensor(hand_state, street, action_config, device)?;
+
+        Self::choose_action_from_net(&proba_tensor, valid_actions_mask, no_invalid, rng)
+    }
+
+    fn get_action_probabilities(
+        &self,
+        hand_state: &HandState,
+        valid_actions_mask: &[bool],
+        street: u8,
+        action_config: &crate::game::action::ActionConfig,
+        device: &candle_core::Device,
+        no_invalid: bool,
+    ) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+        let proba_tensor = self.get_proba_tensor(hand_state, street, action_config, device)?;
+
+        Self::get_probabilities_from_net(&proba_tensor, valid_actions_mask, no_invalid)
+    }
+}
+
+impl AgentNetwork {
+    pub fn new(network: PokerNetwork) -> AgentNetwork {
+        AgentNetwork { network }
+    }
+
+    fn get_proba_tensor(
+        &self,
+        hand_state: &HandState,
+        street: u8,
+        action_config: &crate::game::action::ActionConfig,
+        device: &candle_core::Device,
+    ) -> Result<Tensor, Box<dyn std::error::Error>> {
         let (card_tensor, action_tensor) = hand_state.to_input(
             street,
             action_config,
@@ -26,31 +60,57 @@ impl Agent for AgentNetwork {
             hand_state.action_states.len(),
         )?;
 
-        let proba_tensor = self
+        Ok(self
             .network
             .forward_embedding_actor(
                 &card_tensor.unsqueeze(0)?,
                 &action_tensor.unsqueeze(0)?,
                 false,
             )?
-            .detach();
-
-        Self::choose_action_from_net(&proba_tensor, valid_actions_mask, no_invalid)
-    }
-}
-
-impl AgentNetwork {
-    pub fn new(network: PokerNetwork) -> AgentNetwork {
-        AgentNetwork { network }
+            .detach())
     }
 
     pub fn choose_action_from_net(
         proba_tensor: &Tensor,
         valid_actions_mask: &[bool],
         no_invalid: bool,
+        rng: &mut StdRng,
     ) -> Result<usize, Box<dyn std::error::Error>> {
+        let probas =
+            Self::get_probabilities_from_net(proba_tensor, valid_actions_mask, no_invalid)?;
+
+        // Choose action based on the probability distribution
+        // let random_float_0_1: f32 = rng.gen();
+        // let mut sum: f32 = 0.0;
+        // let mut action_index: usize = 0;
+        // for (i, p) in probas.iter().enumerate() {
+        //     sum += p;
+        //     if sum > random_float_0_1 {
+        //         action_index = i;
+        //         break;
+        //     }
+        // }
+        let distribution = rand::distributions::WeightedIndex::new(probas).unwrap();
+        let action_index = distribution.sample(rng);
+
+        if no_invalid
+            && (action_index >= valid_actions_mask.len() || !valid_actions_mask[action_index])
+        {
+            // println!("Invalid action index: {}", action_index);
+            // println!("Probas: {:?}", probas);
+            return Err("Invalid action index".into());
+        }
+
+        Ok(action_index)
+    }
+
+    pub fn get_probabilities_from_net(
+        proba_tensor: &Tensor,
+        valid_actions_mask: &[bool],
+        no_invalid: bool,
+    ) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
         // Apply valid action mask to tensor
-        let mut probas = proba_tensor.squeeze(0)?.to_vec1()?;
+        let mut probas: Vec<f32> = proba_tensor.squeeze(0)?.to_vec1()?;
         for i in 0..probas.len() {
             if no_invalid && (i >= valid_actions_mask.len() || !valid_actions_mask[i]) {
                 probas[i] = 0.0;
@@ -77,29 +137,33 @@ impl AgentNetwork {
             }
         }
 
-        // Choose action based on the probability distribution
-        let mut rng = rand::thread_rng();
-        // let random_float_0_1: f32 = rng.gen();
-        // let mut sum: f32 = 0.0;
-        // let mut action_index: usize = 0;
-        // for (i, p) in probas.iter().enumerate() {
-        //     sum += p;
-        //     if sum > random_float_0_1 {
-        //         action_index = i;
-        //         break;
-        //     }
-        // }
-        let distribution = rand::distributions::WeightedIndex::new(probas).unwrap();
-        let action_index = distribution.sample(&mut rng);
+        Ok(probas)
+    }
+}
 
-        if no_invalid
-            && (action_index >= valid_actions_mask.len() || !valid_actions_mask[action_index])
-        {
-            // println!("Invalid action index: {}", action_index);
-            // println!("Probas: {:?}", probas);
-            return Err("Invalid action index".into());
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::Agent;
+    use crate::game::action::ActionConfig;
+    use crate::game::tree::Tree;
+    use candle_core::Device;
+    use std::sync::Arc;
 
-        Ok(action_index)
+    #[test]
+    fn networks_play_the_toy_games() {
+        for action_config in [ActionConfig::new_kuhn(), ActionConfig::new_leduc()] {
+            let network =
+                PokerNetwork::new(2, action_config.clone(), Device::Cpu, Device::Cpu, false)
+                    .unwrap();
+            let agent: Arc<Box<dyn Agent>> = Arc::new(Box::new(AgentNetwork::new(network)));
+            let agents = vec![agent.clone(), agent];
+            let mut tree = Tree::new(2, &action_config, 0);
+            for _ in 0..10 {
+                let hand_result = tree.play_one_hand(&agents, &Device::Cpu, true).unwrap();
+                assert_eq!(hand_result.chip_results.iter().sum::<i32>(), 0);
+                tree.move_button();
+            }
+        }
     }
 }
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use super::agent_random::AgentRandom;
 use super::Agent;
 
+use rand::rngs::StdRng;
 use rand::Rng;
 
 pub struct AgentPool {
@@ -39,13 +40,11 @@ impl AgentPool {
     //     self.agents.push(Arc::new(agent));
     // }
 
-    pub fn get_agent(&self) -> (i32, Arc<Box<dyn Agent>>) {
+    pub fn get_agent(&self, rng: &mut StdRng) -> (i32, Arc<Box<dyn Agent>>) {
         if self.agents.is_empty() {
             return (-1, Arc::clone(&self.agent_random));
         }
 
-        let mut rng = rand::thread_rng();
-
         // Return random agent 25% of the time when we have less than 3 agents
         if self.agents.len() < 3 {
             let random_float_0_1: f32 = rng.gen();
@@ -1,6 +1,7 @@
 use super::Agent;
 use crate::game::hand_state::HandState;
 
+use rand::rngs::StdRng;
 use rand::Rng;
 
 pub struct AgentRandom {}
@@ -14,8 +15,8 @@ impl Agent for AgentRandom {
         _action_config: &crate::game::action::ActionConfig,
         _device: &candle_core::Device,
         no_invalid: bool,
+        rng: &mut StdRng,
     ) -> Result<usize, Box<dyn std::error::Error>> {
-        let mut rng = rand::thread_rng();
         let mut action_index = rng.gen_range(0..valid_actions_mask.len());
 
         while no_invalid && !valid_actions_mask[action_index] {
@@ -24,4 +25,31 @@ impl Agent for AgentRandom {
 
         Ok(action_index)
     }
+
+    fn get_action_probabilities(
+        &self,
+        _hand_state: &HandState,
+        valid_actions_mask: &[bool],
+        _street: u8,
+        _action_config: &crate::game::action::ActionConfig,
+        _device: &candle_core::Device,
+        no_invalid: bool,
+    ) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+        let action_count = if no_invalid {
+            valid_actions_mask.iter().filter(|&&valid| valid).count()
+        } else {
+            valid_actions_mask.len()
+        };
+
+        Ok(valid_actions_mask
+            .iter()
+            .map(|&valid| {
+                if valid || !no_invalid {
+                    1.0 / action_count as f32
+                } else {
+                    0.0
+                }
+            })
+            .collect())
+    }
 }
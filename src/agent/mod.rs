@@ -1,10 +1,12 @@
 use crate::game::hand_state::HandState;
+use rand::rngs::StdRng;
 
 // pub trait AgentClone {
 //     fn clone_box(&self) -> Box<dyn Agent>;
 // }
 
 pub trait Agent: Sync + Send {
+    #[allow(clippy::too_many_arguments)]
     fn choose_action(
         &self,
         hand_state: &HandState,
@@ -13,10 +15,24 @@ pub trait Agent: Sync + Send {
         action_config: &crate::game::action::ActionConfig,
         device: &candle_core::Device,
         no_invalid: bool,
+        rng: &mut StdRng,
     ) -> Result<usize, Box<dyn std::error::Error>>;
+
+    // Probability of each action slot, used where the whole policy is needed (best response)
+    fn get_action_probabilities(
+        &self,
+        hand_state: &HandState,
+        valid_actions_mask: &[bool],
+        street: u8,
+        action_config: &crate::game::action::ActionConfig,
+        device: &candle_core::Device,
+        no_invalid: bool,
+    ) -> Result<Vec<f32>, Box<dyn std::error::Error>>;
 }
 
 pub mod agent_network;
 pub mod agent_pool;
 pub mod agent_random;
+pub mod session;
+pub mod sit_and_go;
 pub mod tournament;
@@ -0,0 +1,232 @@
+use std::sync::Arc;
+
+use candle_core::Device;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::game::{action::ActionConfig, tree::Tree};
+
+use super::Agent;
+
+// What happens to a player who lost its whole stack. A stack too short for the blinds and
+// antes posts what it has and plays the hand all-in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RebuyPolicy {
+    // No rebuy, a busted player sits out and the others play on
+    Never,
+    // A busted player buys in again for buy_in
+    Rebuy,
+    // Before every hand, a stack under buy_in is topped up to buy_in
+    TopUp,
+}
+
+pub struct SessionResult {
+    // Stack of each player before the first hand and after every hand
+    pub stack_trajectories: Vec<Vec<u32>>,
+    // Chips each player brought to the table, rebuys and top-ups included
+    pub bought_in: Vec<u32>,
+    pub rebuy_counts: Vec<u32>,
+    pub hands_played: u32,
+    // Hand before which each player busted and sat out, None if it stayed to the end
+    pub busted_hands: Vec<Option<u32>>,
+}
+
+impl SessionResult {
+    // Chips won by each player over the session
+    pub fn get_net_results(&self) -> Vec<i64> {
+        self.stack_trajectories
+            .iter()
+            .zip(self.bought_in.iter())
+            .map(|(trajectory, bought_in)| *trajectory.last().unwrap() as i64 - *bought_in as i64)
+            .collect()
+    }
+
+    pub fn print(&self) {
+        println!("Session of {} hands", self.hands_played);
+        let net_results = self.get_net_results();
+        for (player, trajectory) in self.stack_trajectories.iter().enumerate() {
+            let lowest = trajectory.iter().min().unwrap();
+            let highest = trajectory.iter().max().unwrap();
+            println!(
+                "Player {}: stack {} (lowest {}, highest {}), bought in {} ({} rebuys), net {}",
+                player,
+                trajectory.last().unwrap(),
+                lowest,
+                highest,
+                self.bought_in[player],
+                self.rebuy_counts[player],
+                net_results[player]
+            );
+            if let Some(hand) = self.busted_hands[player] {
+                println!("Player {} busted and sat out from hand {}", player, hand);
+            }
+        }
+    }
+}
+
+// Cash game: consecutive hands at the same table, stacks are carried over from one hand to the
+// next and the button moves every hand
+pub struct Session {
+    action_config: ActionConfig,
+    rebuy_policy: RebuyPolicy,
+    seed: u64,
+}
+
+impl Session {
+    pub fn new(action_config: ActionConfig, rebuy_policy: RebuyPolicy, seed: u64) -> Session {
+        Session {
+            action_config,
+            rebuy_policy,
+            seed,
+        }
+    }
+
+    // Agent i plays seat i. Stops after hand_count hands, or when a single player has chips left.
+    pub fn play(
+        &self,
+        agents: &[Arc<Box<dyn Agent>>],
+        device: &Device,
+        hand_count: u32,
+    ) -> Result<SessionResult, Box<dyn std::error::Error>> {
+        self.action_config.validate()?;
+        let player_count = self.action_config.player_count as usize;
+        if agents.len() != player_count {
+            return Err("One agent is needed per player".into());
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut tree = Tree::new(player_count as u32, &self.action_config, rng.gen());
+
+        let mut stacks = self.action_config.create_starting_stacks(&mut rng)?;
+        let mut result = SessionResult {
+            stack_trajectories: stacks.iter().map(|stack| vec![*stack]).collect(),
+            bought_in: stacks.clone(),
+            rebuy_counts: vec![0; player_count],
+            hands_played: 0,
+            busted_hands: vec![None; player_count],
+        };
+
+        let buy_in = self.action_config.buy_in;
+        for _ in 0..hand_count {
+            for (player, stack) in stacks.iter_mut().enumerate() {
+                match self.rebuy_policy {
+                    RebuyPolicy::Never => {
+                        if *stack == 0 && result.busted_hands[player].is_none() {
+                            result.busted_hands[player] = Some(result.hands_played);
+                        }
+                    }
+                    RebuyPolicy::Rebuy => {
+                        if *stack == 0 {
+                            *stack = buy_in;
+                            result.bought_in[player] += buy_in;
+                            result.rebuy_counts[player] += 1;
+                        }
+                    }
+                    RebuyPolicy::TopUp => {
+                        if *stack < buy_in {
+                            result.bought_in[player] += buy_in - *stack;
+                            result.rebuy_counts[player] += 1;
+                            *stack = buy_in;
+                        }
+                    }
+                }
+            }
+            if stacks.iter().filter(|stack| **stack > 0).count() < 2 {
+                break;
+            }
+
+            // The seats stay the same, busted players sit out with an empty stack. The button
+            // moves before every hand but the first, past the seats sitting out.
+            tree.set_stacks(Some(stacks.clone()))?;
+            if result.hands_played > 0 {
+                tree.move_button();
+            }
+            let hand_result = tree.play_one_hand(agents, device, true)?;
+
+            for ((stack, trajectory), chip_result) in stacks
+                .iter_mut()
+                .zip(result.stack_trajectories.iter_mut())
+                .zip(hand_result.chip_results.iter())
+            {
+                *stack = (*stack as i32 + chip_result) as u32;
+                trajectory.push(*stack);
+            }
+            result.hands_played += 1;
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::agent_random::AgentRandom;
+
+    #[test]
+    fn busted_players_sit_out_and_the_others_play_on() {
+        let action_config = ActionConfig::new(6, 1000, 20, 9);
+        let agents: Vec<Arc<Box<dyn Agent>>> = (0..6)
+            .map(|_| Arc::new(Box::new(AgentRandom {}) as Box<dyn Agent>))
+            .collect();
+        let session = Session::new(action_config, RebuyPolicy::Never, 5);
+        let result = session.play(&agents, &Device::Cpu, 1000).unwrap();
+
+        let first_bust = result.busted_hands.iter().flatten().min().copied().unwrap();
+        assert!(first_bust < result.hands_played);
+        for (trajectory, busted_hand) in result
+            .stack_trajectories
+            .iter()
+            .zip(result.busted_hands.iter())
+        {
+            assert_eq!(trajectory.len() as u32, result.hands_played + 1);
+            // A busted player sits out with no chips until the end
+            if let Some(hand) = busted_hand {
+                assert!(trajectory[*hand as usize..].iter().all(|s| *s == 0));
+            }
+        }
+        assert_eq!(result.get_net_results().iter().sum::<i64>(), 0);
+        assert_eq!(result.rebuy_counts, vec![0; 6]);
+    }
+
+    #[test]
+    fn short_stacks_play_their_all_in_hands() {
+        // Stacks of 5 big blinds soon get shorter than the blinds, the antes make odd stacks
+        let mut action_config = ActionConfig::new(3, 100, 20, 9);
+        action_config.ante = 3;
+        let agents: Vec<Arc<Box<dyn Agent>>> = (0..3)
+            .map(|_| Arc::new(Box::new(AgentRandom {}) as Box<dyn Agent>))
+            .collect();
+
+        let mut short_stack_hands = 0;
+        for seed in 0..20 {
+            let session = Session::new(action_config.clone(), RebuyPolicy::Never, seed);
+            let result = session.play(&agents, &Device::Cpu, 200).unwrap();
+
+            for (trajectory, busted_hand) in result
+                .stack_trajectories
+                .iter()
+                .zip(result.busted_hands.iter())
+            {
+                // Only an empty stack is busted
+                let busted_hand = busted_hand.unwrap_or(u32::MAX) as usize;
+                if busted_hand < trajectory.len() {
+                    assert_eq!(trajectory[busted_hand], 0);
+                }
+                for (hand, stack) in trajectory[..result.hands_played as usize]
+                    .iter()
+                    .enumerate()
+                {
+                    if *stack > 0 && *stack < 20 {
+                        // The short stack is dealt in, its chips are in play
+                        assert!(busted_hand > hand);
+                        if trajectory[hand + 1] != *stack {
+                            short_stack_hands += 1;
+                        }
+                    }
+                }
+            }
+        }
+        assert!(short_stack_hands > 0);
+    }
+}
@@ -0,0 +1,350 @@
+use std::sync::Arc;
+
+use candle_core::Device;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::game::action::{ActionConfig, BettingStructure};
+use crate::game::icm;
+use crate::game::tree::Tree;
+
+use super::Agent;
+
+// Forced bets of a level, played for hand_count hands. The last level lasts until the end.
+#[derive(Clone, Debug)]
+pub struct BlindLevel {
+    pub small_blind: u32,
+    pub big_blind: u32,
+    pub ante: u32,
+    pub hand_count: u32,
+}
+
+impl BlindLevel {
+    // Blinds grow by half at every level, antes of a tenth of the big blind start at the fourth level
+    pub fn create_levels(big_blind: u32, hand_count: u32, level_count: u32) -> Vec<BlindLevel> {
+        let mut levels = Vec::new();
+        let mut level_big_blind = big_blind as f32;
+        for level in 0..level_count {
+            let big_blind = level_big_blind.round() as u32;
+            levels.push(BlindLevel {
+                small_blind: big_blind / 2,
+                big_blind,
+                ante: if level >= 3 { big_blind / 10 } else { 0 },
+                hand_count,
+            });
+            level_big_blind *= 1.5;
+        }
+        levels
+    }
+}
+
+pub struct SitAndGoResult {
+    // Finishing place of each player, 1 is the winner
+    pub places: Vec<u32>,
+    pub prizes: Vec<f32>,
+    // Hand in which each player was eliminated, None for the winner
+    pub elimination_hands: Vec<Option<u32>>,
+    pub hands_played: u32,
+    // Stack of each player before the first hand and after every hand, 0 once eliminated
+    pub stack_trajectories: Vec<Vec<u32>>,
+    // Prize equity of each player before the first hand and after every hand
+    pub equity_trajectories: Vec<Vec<f32>>,
+}
+
+impl SitAndGoResult {
+    pub fn print(&self) {
+        println!("Sit-and-go of {} hands", self.hands_played);
+        for player in 0..self.places.len() {
+            match self.elimination_hands[player] {
+                Some(hand) => println!(
+                    "Player {}: place {}, prize {}, eliminated in hand {}",
+                    player, self.places[player], self.prizes[player], hand
+                ),
+                None => println!(
+                    "Player {}: place {}, prize {}",
+                    player, self.places[player], self.prizes[player]
+                ),
+            }
+        }
+    }
+}
+
+// Single-table tournament: every player starts with buy_in chips, the blinds go up by levels and
+// players are eliminated when they lose their stack. The last players are paid the payouts.
+// Eliminated players sit out with an empty stack, the table keeps its seats so network agents
+// always get the same input size.
+pub struct SitAndGo {
+    action_config: ActionConfig,
+    blind_levels: Vec<BlindLevel>,
+    payouts: Vec<f32>,
+    seed: u64,
+}
+
+impl SitAndGo {
+    // The blinds, antes and payouts of action_config are replaced by the ones of the tournament
+    pub fn new(
+        action_config: ActionConfig,
+        blind_levels: Vec<BlindLevel>,
+        payouts: Vec<f32>,
+        seed: u64,
+    ) -> SitAndGo {
+        SitAndGo {
+            action_config,
+            blind_levels,
+            payouts,
+            seed,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.blind_levels.is_empty() {
+            return Err("At least one blind level is needed".into());
+        }
+        for level in self.blind_levels.iter() {
+            if level.big_blind == 0 || level.small_blind > level.big_blind {
+                return Err(format!("Invalid blind level {:?}", level).into());
+            }
+            if level.hand_count == 0 {
+                return Err(format!("Blind level {:?} has no hands", level).into());
+            }
+        }
+        if self.payouts.is_empty() {
+            return Err("At least one place must be paid".into());
+        }
+        if self.payouts.len() > self.action_config.player_count as usize {
+            return Err("More places are paid than there are players".into());
+        }
+
+        // Every level must be a valid game. Only the first one must be deeper than the forced bets,
+        // later the stacks have grown and a short stack posts what it has.
+        let player_count = self.action_config.player_count as usize;
+        for level_index in 0..self.blind_levels.len() {
+            let mut action_config = self.get_icm_action_config(level_index, player_count);
+            if level_index > 0 {
+                action_config.buy_in = self.action_config.buy_in * player_count as u32;
+            }
+            action_config.validate()?;
+        }
+        Ok(())
+    }
+
+    // Agent i plays seat i, until one player has all the chips
+    pub fn play(
+        &self,
+        agents: &[Arc<Box<dyn Agent>>],
+        device: &Device,
+    ) -> Result<SitAndGoResult, Box<dyn std::error::Error>> {
+        self.validate()?;
+        let player_count = self.action_config.player_count as usize;
+        if agents.len() != player_count {
+            return Err("One agent is needed per player".into());
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+
+        let mut stacks = vec![self.action_config.buy_in; player_count];
+        let mut players_left: Vec<usize> = (0..player_count).collect();
+        let mut result = SitAndGoResult {
+            places: vec![1; player_count],
+            prizes: vec![0.0; player_count],
+            elimination_hands: vec![None; player_count],
+            hands_played: 0,
+            stack_trajectories: stacks.iter().map(|stack| vec![*stack]).collect(),
+            equity_trajectories: Vec::new(),
+        };
+        result.equity_trajectories = self
+            .get_icm_equities(&stacks, &result.places)
+            .into_iter()
+            .map(|equity| vec![equity])
+            .collect();
+
+        let mut button = player_count - 1;
+        let mut level_index = 0;
+        let mut level_hands = 0;
+        while players_left.len() > 1 {
+            if level_hands == self.blind_levels[level_index].hand_count
+                && level_index + 1 < self.blind_levels.len()
+            {
+                level_index += 1;
+                level_hands = 0;
+            }
+            let action_config = self.create_action_config(&self.blind_levels[level_index]);
+
+            // Play one hand, the eliminated players sit out
+            let mut tree = Tree::new(player_count as u32, &action_config, rng.gen());
+            tree.set_button(button as u32);
+            let starting_stacks = stacks.clone();
+            tree.set_stacks(Some(starting_stacks.clone()))?;
+            let hand_result = tree.play_one_hand(agents, device, true)?;
+
+            for ((stack, trajectory), chip_result) in stacks
+                .iter_mut()
+                .zip(result.stack_trajectories.iter_mut())
+                .zip(hand_result.chip_results.iter())
+            {
+                *stack = (*stack as i32 + chip_result) as u32;
+                trajectory.push(*stack);
+            }
+
+            // Players busted in the same hand are ranked by their stack at the start of the hand
+            let mut busted: Vec<(usize, u32)> = players_left
+                .iter()
+                .filter(|player| stacks[**player] == 0)
+                .map(|player| (*player, starting_stacks[*player]))
+                .collect();
+            busted.sort_by_key(|(_, starting_stack)| *starting_stack);
+            for (i, (player, _)) in busted.iter().enumerate() {
+                result.places[*player] = (players_left.len() - i) as u32;
+                result.elimination_hands[*player] = Some(result.hands_played);
+            }
+            let equities = self.get_icm_equities(&stacks, &result.places);
+            for (trajectory, equity) in result.equity_trajectories.iter_mut().zip(equities) {
+                trajectory.push(equity);
+            }
+
+            // The button moves to the next player left
+            players_left.retain(|player| stacks[*player] > 0);
+            button = (1..=player_count)
+                .map(|i| (button + i) % player_count)
+                .find(|player| players_left.contains(player))
+                .unwrap();
+
+            result.hands_played += 1;
+            level_hands += 1;
+        }
+
+        for player in 0..player_count {
+            result.prizes[player] = self
+                .payouts
+                .get(result.places[player] as usize - 1)
+                .copied()
+                .unwrap_or(0.0);
+        }
+
+        Ok(result)
+    }
+
+    // Prize equity of each player for the given stacks, 0 chips for the eliminated players.
+    // Eliminated players already have their place and get its prize, the players left share the
+    // places left by ICM.
+    fn get_icm_equities(&self, stacks: &[u32], places: &[u32]) -> Vec<f32> {
+        let stacks_left: Vec<f32> = stacks
+            .iter()
+            .filter(|stack| **stack > 0)
+            .map(|stack| *stack as f32)
+            .collect();
+        let payouts = &self.payouts[..stacks_left.len().min(self.payouts.len())];
+        let equities_left = icm::get_icm_equities(&stacks_left, payouts);
+
+        let mut equities_left = equities_left.into_iter();
+        stacks
+            .iter()
+            .zip(places.iter())
+            .map(|(stack, place)| {
+                if *stack > 0 {
+                    equities_left.next().unwrap()
+                } else {
+                    self.payouts
+                        .get(*place as usize - 1)
+                        .copied()
+                        .unwrap_or(0.0)
+                }
+            })
+            .collect()
+    }
+
+    // Game to train or evaluate agents at a level when players_left players are still in the
+    // tournament: the rewards are ICM rewards over the places left instead of chips. The table
+    // keeps all its seats, the eliminated players sit out with an empty stack.
+    pub fn get_icm_action_config(&self, level_index: usize, players_left: usize) -> ActionConfig {
+        let mut action_config = self.create_action_config(&self.blind_levels[level_index]);
+        action_config.payouts = self.payouts[..players_left.min(self.payouts.len())].to_vec();
+        action_config
+    }
+
+    // Game played at a level, fixed-limit bets follow the big blind. Payouts are left empty, the
+    // stacks are moved by chip rewards.
+    fn create_action_config(&self, level: &BlindLevel) -> ActionConfig {
+        let mut action_config = self.action_config.clone();
+        action_config.small_blind = level.small_blind;
+        action_config.big_blind = level.big_blind;
+        action_config.ante = level.ante;
+        action_config.payouts = Vec::new();
+        if let BettingStructure::FixedLimit { raise_cap, .. } = action_config.betting_structure {
+            action_config.betting_structure = BettingStructure::FixedLimit {
+                small_bet: level.big_blind,
+                big_bet: 2 * level.big_blind,
+                raise_cap,
+            };
+        }
+        action_config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::agent_network::AgentNetwork;
+    use crate::agent::agent_random::AgentRandom;
+    use crate::model::poker_network::PokerNetwork;
+
+    #[test]
+    fn equities_end_at_the_prizes() {
+        let action_config = ActionConfig::new(4, 500, 20, 9);
+        let agents: Vec<Arc<Box<dyn Agent>>> = (0..4)
+            .map(|_| Arc::new(Box::new(AgentRandom {}) as Box<dyn Agent>))
+            .collect();
+        let payouts = vec![0.5, 0.3, 0.2];
+        let sit_and_go = SitAndGo::new(
+            action_config,
+            BlindLevel::create_levels(20, 10, 5),
+            payouts.clone(),
+            3,
+        );
+        let result = sit_and_go.play(&agents, &Device::Cpu).unwrap();
+
+        // Equities are equal at the start and always share the whole prize pool
+        for equity in result.equity_trajectories.iter() {
+            assert_eq!(equity.len() as u32, result.hands_played + 1);
+            assert!((equity[0] - 0.25).abs() < 1e-5);
+        }
+        for hand in 0..=result.hands_played as usize {
+            let total: f32 = result.equity_trajectories.iter().map(|e| e[hand]).sum();
+            assert!((total - 1.0).abs() < 1e-5);
+        }
+
+        let mut places = result.places.clone();
+        places.sort();
+        assert_eq!(places, vec![1, 2, 3, 4]);
+        for (player, equity) in result.equity_trajectories.iter().enumerate() {
+            assert!((equity.last().unwrap() - result.prizes[player]).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn network_agents_keep_their_seats_after_eliminations() {
+        let action_config = ActionConfig::new(3, 200, 20, 9);
+        let network =
+            PokerNetwork::new(3, action_config.clone(), Device::Cpu, Device::Cpu, false).unwrap();
+        let agent: Arc<Box<dyn Agent>> = Arc::new(Box::new(AgentNetwork::new(network)));
+        let agents = vec![agent.clone(), agent.clone(), agent];
+        let sit_and_go = SitAndGo::new(
+            action_config,
+            BlindLevel::create_levels(40, 2, 6),
+            vec![0.7, 0.3],
+            0,
+        );
+
+        // Heads-up games are still played at the table of 3 seats
+        let icm_action_config = sit_and_go.get_icm_action_config(5, 2);
+        assert_eq!(icm_action_config.player_count, 3);
+        assert_eq!(icm_action_config.payouts, vec![0.7, 0.3]);
+
+        let result = sit_and_go.play(&agents, &Device::Cpu).unwrap();
+        let mut places = result.places.clone();
+        places.sort();
+        assert_eq!(places, vec![1, 2, 3]);
+        let first_elimination = result.elimination_hands.iter().flatten().min().unwrap();
+        assert!(first_elimination + 1 < result.hands_played);
+    }
+}
@@ -1,13 +1,12 @@
 use std::{
     cmp::Ordering,
-    sync::{Arc, Mutex, TryLockError},
-    thread,
-    time::Duration,
+    sync::{Arc, Mutex},
 };
 
 use candle_core::Device;
 use indicatif::{ProgressBar, ProgressStyle};
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use threadpool::ThreadPool;
 
 use crate::{
@@ -30,6 +29,26 @@ struct AgentTournament {
     over_max_rating: bool,
 }
 
+// Rating of an agent at the start of a round
+struct Rating {
+    elo: f32,
+    hands_played: usize,
+    over_max_rating: bool,
+}
+
+impl Rating {
+    // Ratings of agents that played a lot or went over the max rating move slower
+    fn get_elo_k(&self, elo_k: f32) -> f32 {
+        if self.over_max_rating {
+            elo_k * 0.25
+        } else if self.hands_played > 100000 {
+            elo_k * 0.5
+        } else {
+            elo_k
+        }
+    }
+}
+
 pub struct Tournament {
     agents: Vec<Arc<Mutex<AgentTournament>>>,
     player_count: u32,
@@ -97,222 +116,91 @@ impl Tournament {
         self.agents.len()
     }
 
-    pub fn play(&mut self, total_hands: usize) {
+    // Games are played by rounds, every agent plays one game per round. The games of a round are
+    // matched with the ratings at the start of the round and each one has its own seed, so they
+    // can be played by any number of threads. The rating changes are added once the round is over,
+    // in the order of the games: the same seed gives the same ratings.
+    pub fn play(&mut self, total_hands: usize, seed: u64) {
         // Dynamically select agents based on Elo for each game
         self.agents
             .sort_by(|a, b| b.lock().unwrap().elo.total_cmp(&a.lock().unwrap().elo));
 
-        let agents_tournament_base = Arc::new(Mutex::new(self.agents.clone()));
-        let n_workers = num_cpus::get() * 3 / 4;
+        let agent_networks: Arc<Vec<Arc<Box<dyn Agent>>>> = Arc::new(
+            self.agents
+                .iter()
+                .map(|agent| Arc::clone(&agent.lock().unwrap().agent_network))
+                .collect(),
+        );
+        let action_config = Arc::new(self.action_config.clone());
+        let n_workers = (num_cpus::get() * 3 / 4).max(1);
         let thread_pool = ThreadPool::new(n_workers);
-        let elo_k = 40.0;
+        let mut rng = StdRng::seed_from_u64(seed);
 
-        let batch_size = (self.agents.len() as f32 / n_workers as f32).ceil() as usize;
-        let rounds = total_hands / self.agents.len();
+        let agent_count = self.agents.len();
+        let rounds = total_hands / agent_count;
 
-        let progress_bar = Arc::new(ProgressBar::new(rounds as u64 * self.agents.len() as u64));
+        let progress_bar = Arc::new(ProgressBar::new(rounds as u64 * agent_count as u64));
         progress_bar.set_style(ProgressStyle::default_bar()
         .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos:>7}/{len:7} ({eta})").unwrap()
         .progress_chars("#>-"));
 
-        for worker in 0..n_workers {
-            let player_count = self.player_count;
-            let agents_length = self.agents.len();
-            let start = worker * batch_size;
-            let end = ((worker + 1) * batch_size).min(agents_length);
-            let action_config = self.action_config.clone();
-            let device = self.device.clone();
-            let agents_tournament = Arc::clone(&agents_tournament_base);
-            let pb = Arc::clone(&progress_bar);
-
-            thread_pool.execute(move || {
-                let mut rng = rand::thread_rng();
-
-                // Run the loop N times
-                for _ in 0..rounds {
-                    for agent_index in start..end {
-                        pb.inc(1);
-                        let mut agents_game: Vec<(usize, Arc<Mutex<AgentTournament>>)>;
-
-                        // Lock the tournament agents to prepare the match
-                        // Choose agents with closer Elo for fairer matches
-                        {
-                            let agents_locked = agents_tournament.lock().unwrap();
-                            let current_agent = Arc::clone(&agents_locked[agent_index]);
-                            let chosen_elo = current_agent.lock().unwrap().elo;
-
-                            // Step 1: Sort the remaining players by their elo difference to the chosen player
-                            let mut elo_differences = agents_locked
-                                .iter()
-                                .enumerate()
-                                .filter_map(|(index, agent)| {
-                                    if index != agent_index {
-                                        let agent = agent.lock().unwrap();
-                                        Some((index, (chosen_elo - agent.elo).abs()))
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .collect::<Vec<_>>();
-
-                            // Randomize elo differences to avoid always choosing the same players, especially at the start
-                            elo_differences.shuffle(&mut rng);
-
-                            // Sort by elo difference
-                            elo_differences
-                                .sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
-
-                            // Step 2: Take N-1 agents with the closest elo difference, then add current agent
-                            agents_game = elo_differences
-                                .iter()
-                                .take((player_count - 1) as usize)
-                                .map(|&(index, _)| (index, Arc::clone(&agents_locked[index])))
-                                .collect::<Vec<_>>();
-
-                            agents_game.push((agent_index, current_agent));
-                        }
-
-                        // Assign each agent a random index between 0 and player_count
-                        let mut tree_agents = Vec::new();
-                        let mut indexes = Vec::new();
-                        {
-                            let mut indexes_sorted = (0..player_count as usize).collect::<Vec<_>>();
-                            for _ in 0..player_count {
-                                let mut rng = rand::thread_rng();
-                                let vec_index = rng.gen_range(0..indexes_sorted.len());
-                                let agent_index = indexes_sorted[vec_index];
-                                let agent = agents_game[agent_index].1.lock().unwrap();
-                                tree_agents.push(Arc::clone(&agent.agent_network));
-                                indexes.push(agent_index);
-                                indexes_sorted.remove(vec_index);
-                            }
-                        }
-
-                        // Play one game
-                        let mut total_won = vec![0.0f32; player_count as usize];
-                        let mut tree = Tree::new(player_count, &action_config);
-                        {
-                            let rewards = match tree.play_one_hand(&tree_agents, &device, true) {
-                                Ok(r) => r,
-                                Err(error) => panic!("ERROR in play_one_hand: {:?}", error),
-                            };
-                            for p in 0..player_count as usize {
-                                total_won[indexes[p]] += rewards[p];
-                            }
-                        }
-
-                        // Lock players & update ELO
-                        {
-                            let mut agents_locked = vec![];
-                            // We need to lock all players at once to avoid deadlocks, or wait if one fails
-                            loop {
-                                for arc_mutex in &agents_game {
-                                    match arc_mutex.1.try_lock() {
-                                        Ok(guard) => agents_locked.push(guard),
-                                        Err(TryLockError::WouldBlock) => {
-                                            agents_locked.clear(); // Release any acquired locks
-                                            thread::sleep(Duration::from_millis(1)); // Prevent busy-waiting
-                                            break;
-                                        }
-                                        Err(_) => panic!("Mutex is poisoned"),
-                                    }
-                                }
-                                if agents_locked.len() == agents_game.len() {
-                                    break; // Successfully locked all Mutexes
-                                }
-                            }
-
-                            // Check each pair of players to determine winner(s) and update ELO
-                            let mut elo_diff = vec![0.0f32; player_count as usize];
-                            for i in 0..player_count as usize {
-                                for j in i + 1..player_count as usize {
-                                    // // We dont want ELO to increase for negative rewards, or decrease for positive rewards
-                                    // if (total_won[i] < -1e-4 && total_won[j] < -1e-4)
-                                    //     || (total_won[i] > 1e-4 && total_won[j] > 1e-4)
-                                    // {
-                                    //     continue;
-                                    // }
-
-                                    let player_i_result =
-                                        if (total_won[i] - total_won[j]).abs() < 1e-4 {
-                                            0.5
-                                        } else if total_won[i] > total_won[j] {
-                                            1.0
-                                        } else {
-                                            0.0
-                                        };
-
-                                    let prob_i = Self::calculate_expected_win_prob(
-                                        agents_locked[i].elo,
-                                        agents_locked[j].elo,
-                                    );
-                                    let prob_j = Self::calculate_expected_win_prob(
-                                        agents_locked[j].elo,
-                                        agents_locked[i].elo,
-                                    );
-
-                                    // Adjust K-factor based on the possible reward in the game
-                                    let max_to_win = action_config.buy_in as f32;
-
-                                    let min_value = if (total_won[i] < -1e-4
-                                        && total_won[j] < -1e-4)
-                                        || (total_won[i] > 1e-4 && total_won[j] > 1e-4)
-                                    {
-                                        (total_won[i] - total_won[j]).abs()
-                                    } else {
-                                        total_won[i].abs().min(total_won[j].abs())
-                                    };
-
-                                    let dyn_elo_k = elo_k * min_value / max_to_win;
-
-                                    let dyn_elo_k_i = if agents_locked[i].over_max_rating {
-                                        dyn_elo_k * 0.25
-                                    } else if agents_locked[i].hands_played > 100000 {
-                                        dyn_elo_k * 0.5
-                                    } else {
-                                        dyn_elo_k
-                                    };
-
-                                    let dyn_elo_k_j = if agents_locked[j].over_max_rating {
-                                        dyn_elo_k * 0.25
-                                    } else if agents_locked[j].hands_played > 100000 {
-                                        dyn_elo_k * 0.5
-                                    } else {
-                                        dyn_elo_k
-                                    };
-
-                                    let elo_diff_i = Self::calculate_elo_diff(
-                                        prob_i,
-                                        player_i_result,
-                                        dyn_elo_k_i,
-                                    );
-                                    let elo_diff_j = Self::calculate_elo_diff(
-                                        prob_j,
-                                        1.0 - player_i_result,
-                                        dyn_elo_k_j,
-                                    );
-
-                                    elo_diff[i] += elo_diff_i;
-                                    elo_diff[j] += elo_diff_j;
-                                }
-                            }
-
-                            // Update ELO
-                            for i in 0..player_count as usize {
-                                agents_locked[i].elo += elo_diff[i];
-                                agents_locked[i].hands_played += 1;
-                                if agents_locked[i].elo > 2400.0 {
-                                    agents_locked[i].over_max_rating = true;
-                                }
-                            }
+        for _ in 0..rounds {
+            let ratings: Arc<Vec<Rating>> = Arc::new(
+                self.agents
+                    .iter()
+                    .map(|agent| {
+                        let agent = agent.lock().unwrap();
+                        Rating {
+                            elo: agent.elo,
+                            hands_played: agent.hands_played,
+                            over_max_rating: agent.over_max_rating,
                         }
+                    })
+                    .collect(),
+            );
+            // Rating changes of each game, None if its hand was skipped
+            let game_results = Arc::new(Mutex::new(vec![None; agent_count]));
+
+            for agent_index in 0..agent_count {
+                let game_seed: u64 = rng.gen();
+                let player_count = self.player_count;
+                let action_config = Arc::clone(&action_config);
+                let device = self.device.clone();
+                let agent_networks = Arc::clone(&agent_networks);
+                let ratings = Arc::clone(&ratings);
+                let game_results = Arc::clone(&game_results);
+                let pb = Arc::clone(&progress_bar);
+
+                thread_pool.execute(move || {
+                    let mut rng = StdRng::seed_from_u64(game_seed);
+                    let elo_diffs = Self::play_game(
+                        agent_index,
+                        player_count,
+                        &action_config,
+                        &device,
+                        &agent_networks,
+                        &ratings,
+                        &mut rng,
+                    );
+                    game_results.lock().unwrap()[agent_index] = elo_diffs;
+                    pb.inc(1);
+                });
+            }
+            thread_pool.join();
+
+            // Update ELO
+            for elo_diffs in game_results.lock().unwrap().iter().flatten() {
+                for (index, elo_diff) in elo_diffs.iter() {
+                    let mut agent = self.agents[*index].lock().unwrap();
+                    agent.elo += elo_diff;
+                    agent.hands_played += 1;
+                    if agent.elo > 2400.0 {
+                        agent.over_max_rating = true;
                     }
                 }
-            });
+            }
         }
 
-        thread_pool.join();
-
         // Remove all agents with a negative ELO
         self.agents.retain(|agent| agent.lock().unwrap().elo >= 0.0);
 
@@ -326,6 +214,131 @@ impl Tournament {
         }
     }
 
+    // One hand of the agent at agent_index against the agents with the closest ratings. Returns
+    // the rating change of each agent of the game, None if the hand was skipped.
+    fn play_game(
+        agent_index: usize,
+        player_count: u32,
+        action_config: &ActionConfig,
+        device: &Device,
+        agent_networks: &[Arc<Box<dyn Agent>>],
+        ratings: &[Rating],
+        rng: &mut StdRng,
+    ) -> Option<Vec<(usize, f32)>> {
+        let elo_k = 40.0;
+
+        // Choose agents with closer Elo for fairer matches
+        // Step 1: Sort the remaining players by their elo difference to the chosen player
+        let chosen_elo = ratings[agent_index].elo;
+        let mut elo_differences = ratings
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index != agent_index)
+            .map(|(index, rating)| (index, (chosen_elo - rating.elo).abs()))
+            .collect::<Vec<_>>();
+
+        // Randomize elo differences to avoid always choosing the same players, especially at the start
+        elo_differences.shuffle(rng);
+
+        // Sort by elo difference
+        elo_differences.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+
+        // Step 2: Take N-1 agents with the closest elo difference, then add current agent
+        let mut agents_game = elo_differences
+            .iter()
+            .take((player_count - 1) as usize)
+            .map(|&(index, _)| index)
+            .collect::<Vec<_>>();
+        agents_game.push(agent_index);
+
+        // Assign each agent a random index between 0 and player_count
+        let mut tree_agents = Vec::new();
+        let mut indexes = Vec::new();
+        {
+            let mut indexes_sorted = (0..player_count as usize).collect::<Vec<_>>();
+            for _ in 0..player_count {
+                let vec_index = rng.gen_range(0..indexes_sorted.len());
+                let agent_index = indexes_sorted[vec_index];
+                tree_agents.push(Arc::clone(&agent_networks[agents_game[agent_index]]));
+                indexes.push(agent_index);
+                indexes_sorted.remove(vec_index);
+            }
+        }
+
+        // Play one game
+        let mut total_won = vec![0.0f32; player_count as usize];
+        let mut starting_stacks = vec![0u32; player_count as usize];
+        let mut tree = Tree::new(player_count, action_config, rng.gen());
+        tree.set_button(rng.gen_range(0..player_count));
+        {
+            // A broken hand is skipped, the other games of the round go on
+            let rewards = match tree.play_one_hand(&tree_agents, device, true) {
+                Ok(hand_result) => hand_result.rewards,
+                Err(error) => {
+                    println!("Skipped hand, error in play_one_hand: {}", error);
+                    return None;
+                }
+            };
+            let stacks = &tree.hand_state.as_ref().unwrap().stacks;
+            for p in 0..player_count as usize {
+                total_won[indexes[p]] += rewards[p];
+                starting_stacks[indexes[p]] = stacks[p];
+            }
+        }
+
+        // Check each pair of players to determine winner(s) and update ELO
+        let ratings_game: Vec<&Rating> = agents_game.iter().map(|index| &ratings[*index]).collect();
+        let mut elo_diff = vec![0.0f32; player_count as usize];
+        for i in 0..player_count as usize {
+            for j in i + 1..player_count as usize {
+                // // We dont want ELO to increase for negative rewards, or decrease for positive rewards
+                // if (total_won[i] < -1e-4 && total_won[j] < -1e-4)
+                //     || (total_won[i] > 1e-4 && total_won[j] > 1e-4)
+                // {
+                //     continue;
+                // }
+
+                let player_i_result = if (total_won[i] - total_won[j]).abs() < 1e-4 {
+                    0.5
+                } else if total_won[i] > total_won[j] {
+                    1.0
+                } else {
+                    0.0
+                };
+
+                let prob_i =
+                    Self::calculate_expected_win_prob(ratings_game[i].elo, ratings_game[j].elo);
+                let prob_j =
+                    Self::calculate_expected_win_prob(ratings_game[j].elo, ratings_game[i].elo);
+
+                // Adjust K-factor based on the possible reward in the game
+                let max_to_win = starting_stacks[i].min(starting_stacks[j]) as f32;
+
+                let min_value = if (total_won[i] < -1e-4 && total_won[j] < -1e-4)
+                    || (total_won[i] > 1e-4 && total_won[j] > 1e-4)
+                {
+                    (total_won[i] - total_won[j]).abs()
+                } else {
+                    total_won[i].abs().min(total_won[j].abs())
+                };
+
+                let dyn_elo_k = elo_k * min_value / max_to_win;
+
+                let dyn_elo_k_i = ratings_game[i].get_elo_k(dyn_elo_k);
+                let dyn_elo_k_j = ratings_game[j].get_elo_k(dyn_elo_k);
+
+                let elo_diff_i = Self::calculate_elo_diff(prob_i, player_i_result, dyn_elo_k_i);
+                let elo_diff_j =
+                    Self::calculate_elo_diff(prob_j, 1.0 - player_i_result, dyn_elo_k_j);
+
+                elo_diff[i] += elo_diff_i;
+                elo_diff[j] += elo_diff_j;
+            }
+        }
+
+        Some(agents_game.into_iter().zip(elo_diff).collect())
+    }
+
     fn calculate_expected_win_prob(elo_a: f32, elo_b: f32) -> f32 {
         let elo_diff = elo_b - elo_a;
         1.0 / (1.0 + 10.0f32.powf(elo_diff / 400.0))
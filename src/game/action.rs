@@ -1,13 +1,19 @@
-#[derive(Clone, Debug)]
+use super::deal::Deal;
+use super::game_state::{GameState, MAX_PLAYER_COUNT};
+use super::game_variant::GameVariant;
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub enum ActionType {
-    None,
     Fold,
     Call,
     Raise,
     AllIn,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub struct Action {
     pub action_type: ActionType,
     pub raise_index: i8,
@@ -18,7 +24,6 @@ pub struct Action {
 impl Action {
     pub fn _to_print_string(&self) -> String {
         match self.action_type {
-            ActionType::None => String::from("None"),
             ActionType::Fold => String::from("Fold"),
             ActionType::Call => String::from("Call"),
             ActionType::Raise => format!("{}{}", "Raise", self.raise_index),
@@ -27,15 +32,155 @@ impl Action {
     }
 }
 
-#[derive(Clone)]
+// What an action slot means in chips for the player to move
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct LegalAction {
+    pub action_type: ActionType,
+    // Chips put in the pot by the action, 0 for a fold or a check
+    pub amount: u32,
+    // Bet of the player after the action, bets are accumulated over the hand
+    pub total_bet: u32,
+    // Amount as a fraction of the pot before the action
+    pub pot_fraction: f32,
+}
+
+// How a raise slot is sized
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum RaiseSize {
+    // Call, then raise by a fraction of the pot
+    Pot(f32),
+    // Raise to a number of big blinds
+    BigBlind(f32),
+    // Raise to a multiple of the biggest bet
+    LastBet(f32),
+}
+
+impl RaiseSize {
+    // Total bet the raiser reaches with this size
+    pub fn get_raise_to(&self, pot: u32, biggest_bet: u32, big_blind: u32) -> u32 {
+        match self {
+            RaiseSize::Pot(ratio) => biggest_bet + (ratio * pot as f32).round() as u32,
+            RaiseSize::BigBlind(ratio) => (ratio * big_blind as f32).round() as u32,
+            RaiseSize::LastBet(ratio) => (ratio * biggest_bet as f32).round() as u32,
+        }
+    }
+
+    fn get_value(&self) -> f32 {
+        match self {
+            RaiseSize::Pot(ratio) | RaiseSize::BigBlind(ratio) | RaiseSize::LastBet(ratio) => {
+                *ratio
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum BettingStructure {
+    // Raises use the configured raise sizes, all-in is always possible
+    NoLimit,
+    // Raises use the configured raise sizes but can not go over the pot: the raiser calls, then
+    // raises at most the pot after his call. All-in is only possible within that limit.
+    PotLimit,
+    // One raise slot of a small bet preflop and on the flop, a big bet on the turn and river
+    // (from the second street in the toy games).
+    // The number of bets and raises per street is capped, the big blind counts as the first bet.
+    // All-in is only possible for a player who can not call or make a full raise.
+    FixedLimit {
+        small_bet: u32,
+        big_bet: u32,
+        raise_cap: u8,
+    },
+}
+
+// How a showdown is paid when the players were all-in before the last card
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum AllInReward {
+    // The board dealt is played out
+    Runout,
+    // Each player gets his equity of every pot, averaged over all the possible runouts.
+    // A preflop all-in has over a million runouts, its equity is sampled over 1000 of them.
+    Equity,
+    // Equity over a number of random runouts
+    SampledEquity(u32),
+}
+
+// Fee taken by the house from the pots of cash games
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Rake {
+    // Share of the pot taken, between 0 and 100
+    pub percentage: f32,
+    // Most chips taken from a hand, no limit if None
+    pub cap: Option<u32>,
+    // No rake is taken when the hand ends before the flop
+    pub no_flop_no_drop: bool,
+}
+
+impl Rake {
+    pub fn new(percentage: f32, cap: Option<u32>, no_flop_no_drop: bool) -> Rake {
+        Rake {
+            percentage,
+            cap,
+            no_flop_no_drop,
+        }
+    }
+
+    // Chips taken from a finished hand. The part of the last bet nobody called goes back to the
+    // bettor, it is not raked.
+    pub fn get_rake(&self, game_state: &GameState) -> u32 {
+        if self.percentage <= 0.0 || (self.no_flop_no_drop && game_state.street < 2) {
+            return 0;
+        }
+
+        let mut contributions: Vec<u32> = (0..game_state.player_count)
+            .map(|i| game_state.get_contribution(i))
+            .collect();
+        contributions.sort_unstable_by(|a, b| b.cmp(a));
+        let uncalled = contributions[0] - contributions.get(1).copied().unwrap_or(0);
+
+        let rake = (self.percentage / 100.0 * (game_state.get_pot() - uncalled) as f32) as u32;
+        self.cap.map_or(rake, |cap| rake.min(cap))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum StartingStacks {
+    // Every player starts with buy_in
+    BuyIn,
+    // One stack per seat
+    Fixed(Vec<u32>),
+    // Each stack is drawn uniformly between min and max (inclusive)
+    Uniform { min: u32, max: u32 },
+}
+
+#[derive(Clone, Deserialize, Serialize)]
 pub struct ActionConfig {
+    pub game_variant: GameVariant,
     pub player_count: u32,
     pub commited_to_pot_percentage: u8,
     pub buy_in: u32,
+    pub starting_stacks: StartingStacks,
+    pub small_blind: u32,
     pub big_blind: u32,
-    pub preflop_raise_sizes: Vec<f32>,
-    pub postflop_raise_sizes: Vec<f32>,
+    // Dead money posted by every player before the blinds
+    pub ante: u32,
+    // Dead money posted by the big blind only
+    pub big_blind_ante: u32,
+    // If true, the player after the big blind posts a live straddle of 2 big blinds (3+ players)
+    pub straddle: bool,
+    pub betting_structure: BettingStructure,
+    // Raise slots per street, slot i of every street maps to the same network output
+    pub preflop_raise_sizes: Vec<RaiseSize>,
+    pub flop_raise_sizes: Vec<RaiseSize>,
+    pub turn_raise_sizes: Vec<RaiseSize>,
+    pub river_raise_sizes: Vec<RaiseSize>,
     pub max_actions_per_street: u8,
+    // Prize of each place for tournament hands, payouts[0] goes to the winner. If set, rewards
+    // are the change of ICM equity instead of chips. Empty for cash games.
+    pub payouts: Vec<f32>,
+    // Equity rewards remove the variance of the cards still to come from the rewards
+    pub all_in_reward: AllInReward,
+    // Rewards are net of rake, the rewards of a raked hand sum to minus the rake
+    pub rake: Rake,
 }
 
 impl ActionConfig {
@@ -46,13 +191,340 @@ impl ActionConfig {
         max_actions_per_street: u8,
     ) -> ActionConfig {
         ActionConfig {
+            game_variant: GameVariant::Holdem,
             player_count,
             commited_to_pot_percentage: 15,
             buy_in,
+            starting_stacks: StartingStacks::BuyIn,
+            small_blind: big_blind / 2,
             big_blind,
+            ante: 0,
+            big_blind_ante: 0,
+            straddle: false,
+            betting_structure: BettingStructure::NoLimit,
             preflop_raise_sizes: Vec::new(),
-            postflop_raise_sizes: Vec::new(),
+            flop_raise_sizes: Vec::new(),
+            turn_raise_sizes: Vec::new(),
+            river_raise_sizes: Vec::new(),
             max_actions_per_street,
+            payouts: Vec::new(),
+            all_in_reward: AllInReward::Runout,
+            rake: Rake::new(0.0, None, false),
+        }
+    }
+
+    // Kuhn poker: ante 1, a single bet of 1 and no raise. Stacks are deep enough to never be all-in
+    pub fn new_kuhn() -> ActionConfig {
+        let mut action_config = ActionConfig::new(2, 3, 1, 3);
+        action_config.game_variant = GameVariant::Kuhn;
+        action_config.ante = 1;
+        action_config.betting_structure = BettingStructure::FixedLimit {
+            small_bet: 1,
+            big_bet: 1,
+            raise_cap: 1,
+        };
+        action_config
+    }
+
+    // Leduc hold'em: ante 1, bets of 2 then 4 on the second street, a bet and a raise per street
+    pub fn new_leduc() -> ActionConfig {
+        let mut action_config = ActionConfig::new(2, 14, 1, 4);
+        action_config.game_variant = GameVariant::Leduc;
+        action_config.ante = 1;
+        action_config.betting_structure = BettingStructure::FixedLimit {
+            small_bet: 2,
+            big_bet: 4,
+            raise_cap: 2,
+        };
+        action_config
+    }
+
+    // Same sizes on the flop, turn and river
+    pub fn set_postflop_raise_sizes(&mut self, raise_sizes: Vec<RaiseSize>) {
+        self.flop_raise_sizes = raise_sizes.clone();
+        self.turn_raise_sizes = raise_sizes.clone();
+        self.river_raise_sizes = raise_sizes;
+    }
+
+    pub fn get_raise_sizes(&self, street: u8) -> &Vec<RaiseSize> {
+        match street {
+            1 => &self.preflop_raise_sizes,
+            2 => &self.flop_raise_sizes,
+            3 => &self.turn_raise_sizes,
+            _ => &self.river_raise_sizes,
+        }
+    }
+
+    pub fn is_no_limit(&self) -> bool {
+        matches!(self.betting_structure, BettingStructure::NoLimit)
+    }
+
+    // Size of a bet or raise in fixed-limit
+    pub fn get_limit_bet(&self, street: u8) -> u32 {
+        match self.betting_structure {
+            BettingStructure::FixedLimit {
+                small_bet, big_bet, ..
+            } => {
+                if street < self.game_variant.get_big_bet_street() {
+                    small_bet
+                } else {
+                    big_bet
+                }
+            }
+            BettingStructure::NoLimit | BettingStructure::PotLimit => self.big_blind,
+        }
+    }
+
+    // Minimum raise at the start of a street
+    pub fn get_min_raise(&self, street: u8) -> u32 {
+        match self.betting_structure {
+            BettingStructure::FixedLimit { .. } => self.get_limit_bet(street),
+            BettingStructure::NoLimit | BettingStructure::PotLimit => self.big_blind,
+        }
+    }
+
+    // Raise slots used in a street
+    pub fn get_street_raise_slot_count(&self, street: u8) -> usize {
+        match self.betting_structure {
+            BettingStructure::FixedLimit { .. } => 1,
+            BettingStructure::NoLimit | BettingStructure::PotLimit => {
+                self.get_raise_sizes(street).len()
+            }
         }
     }
+
+    // Streets with fewer sizes leave their last slots invalid
+    pub fn get_raise_slot_count(&self) -> usize {
+        (1..=4)
+            .map(|street| self.get_street_raise_slot_count(street))
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Fold, call, every raise slot and all-in
+    pub fn get_action_count(&self) -> usize {
+        3 + self.get_raise_slot_count()
+    }
+
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.player_count < 2 {
+            return Err("At least 2 players are needed".into());
+        }
+        if self.player_count as usize > MAX_PLAYER_COUNT {
+            return Err(format!("At most {} players can sit at a table", MAX_PLAYER_COUNT).into());
+        }
+        if self.big_blind == 0 {
+            return Err("The big blind must be positive".into());
+        }
+        if self.small_blind > self.big_blind {
+            return Err("The small blind can not be bigger than the big blind".into());
+        }
+        if self.commited_to_pot_percentage > 100 {
+            return Err("commited_to_pot_percentage must be between 0 and 100".into());
+        }
+
+        // The board and every hand are dealt at the start of the hand
+        let card_count = self
+            .game_variant
+            .get_board_card_count(self.game_variant.get_street_count())
+            + self.player_count as usize * self.game_variant.get_hole_card_count();
+        if card_count > Deal::create_deck(self.game_variant).len() {
+            return Err(format!(
+                "Not enough cards to deal {} players in {:?}",
+                self.player_count, self.game_variant
+            )
+            .into());
+        }
+
+        let street_names = ["preflop", "flop", "turn", "river"];
+        for (street, street_name) in (1..=4).zip(street_names.iter()) {
+            let raise_sizes = self.get_raise_sizes(street);
+            for (i, raise_size) in raise_sizes.iter().enumerate() {
+                let value = raise_size.get_value();
+                if !value.is_finite() || value <= 0.0 {
+                    return Err(format!(
+                        "Invalid {} raise size {:?}, sizes must be positive",
+                        street_name, raise_size
+                    )
+                    .into());
+                }
+                if !matches!(raise_size, RaiseSize::Pot(_)) && value <= 1.0 {
+                    return Err(format!(
+                        "Invalid {} raise size {:?}, a raise to must be bigger than 1",
+                        street_name, raise_size
+                    )
+                    .into());
+                }
+                if raise_sizes[..i].contains(raise_size) {
+                    return Err(
+                        format!("Duplicate {} raise size {:?}", street_name, raise_size).into(),
+                    );
+                }
+            }
+        }
+
+        if let BettingStructure::FixedLimit {
+            small_bet,
+            big_bet,
+            raise_cap,
+        } = self.betting_structure
+        {
+            if small_bet == 0 || big_bet < small_bet {
+                return Err(
+                    "Fixed-limit needs a positive small bet and a big bet at least as big".into(),
+                );
+            }
+            if raise_cap == 0 {
+                return Err("Fixed-limit needs a raise cap of at least 1".into());
+            }
+            if (1..=4).any(|street| !self.get_raise_sizes(street).is_empty()) {
+                return Err("Raise sizes are not used with fixed-limit betting".into());
+            }
+            if self.has_straddle() {
+                return Err("Straddles are not supported with fixed-limit betting".into());
+            }
+        }
+
+        if self.game_variant.is_toy_game()
+            && (self.player_count != 2
+                || !matches!(self.betting_structure, BettingStructure::FixedLimit { .. }))
+        {
+            return Err(format!(
+                "{:?} is played by 2 players with fixed-limit betting",
+                self.game_variant
+            )
+            .into());
+        }
+
+        if !self.has_blinds() {
+            if self.ante == 0 {
+                return Err(format!(
+                    "{:?} is played with antes only, an ante is needed",
+                    self.game_variant
+                )
+                .into());
+            }
+            if self.big_blind_ante > 0 || self.straddle {
+                return Err(format!(
+                    "{:?} has no blinds, big blind ante and straddle can not be used",
+                    self.game_variant
+                )
+                .into());
+            }
+        }
+
+        if self
+            .payouts
+            .iter()
+            .any(|payout| !payout.is_finite() || *payout < 0.0)
+            || (!self.payouts.is_empty() && self.payouts.iter().sum::<f32>() <= 0.0)
+        {
+            return Err(
+                "Payouts must be positive or zero, with a prize for at least one place".into(),
+            );
+        }
+
+        if !(0.0..=100.0).contains(&self.rake.percentage) {
+            return Err("The rake percentage must be between 0 and 100".into());
+        }
+
+        if self.all_in_reward == AllInReward::SampledEquity(0) {
+            return Err("At least one runout must be sampled".into());
+        }
+
+        match &self.starting_stacks {
+            StartingStacks::BuyIn => {
+                if self.buy_in <= self.get_max_forced_bet() {
+                    return Err("The buy-in must be larger than the blinds and antes".into());
+                }
+            }
+            StartingStacks::Fixed(stacks) => {
+                if stacks.len() != self.player_count as usize {
+                    return Err("One starting stack is needed per player".into());
+                }
+                if stacks.iter().any(|s| *s <= self.get_max_forced_bet()) {
+                    return Err("Starting stacks must be larger than the blinds and antes".into());
+                }
+            }
+            StartingStacks::Uniform { min, max } => {
+                if min > max {
+                    return Err("The minimum starting stack is bigger than the maximum".into());
+                }
+                if *min <= self.get_max_forced_bet() {
+                    return Err("Starting stacks must be larger than the blinds and antes".into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn has_straddle(&self) -> bool {
+        self.straddle && self.player_count > 2
+    }
+
+    pub fn get_straddle(&self) -> u32 {
+        2 * self.big_blind
+    }
+
+    pub fn has_blinds(&self) -> bool {
+        self.game_variant.has_blinds()
+    }
+
+    // Most chips a single player has to put in before acting
+    pub fn get_max_forced_bet(&self) -> u32 {
+        let blind = if !self.has_blinds() {
+            0
+        } else if self.has_straddle() {
+            self.get_straddle()
+                .max(self.big_blind + self.big_blind_ante)
+        } else {
+            self.big_blind + self.big_blind_ante
+        };
+        self.ante + blind
+    }
+
+    // Error if the stacks do not fit the config, it may not have been validated
+    pub fn create_starting_stacks(
+        &self,
+        rng: &mut StdRng,
+    ) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+        let stacks = match &self.starting_stacks {
+            StartingStacks::BuyIn => vec![self.buy_in; self.player_count as usize],
+            StartingStacks::Fixed(stacks) => stacks.clone(),
+            StartingStacks::Uniform { min, max } => (0..self.player_count)
+                .map(|_| rng.gen_range(*min..=*max))
+                .collect(),
+        };
+
+        if stacks.len() != self.player_count as usize {
+            return Err("One starting stack is needed per player".into());
+        }
+        if stacks.iter().any(|s| *s <= self.get_max_forced_bet()) {
+            return Err("Starting stacks must be larger than the blinds and antes".into());
+        }
+
+        Ok(stacks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn invalid_starting_stacks_are_an_error() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut action_config = ActionConfig::new(3, 1000, 20, 9);
+        assert_eq!(
+            action_config.create_starting_stacks(&mut rng).unwrap(),
+            vec![1000; 3]
+        );
+
+        action_config.starting_stacks = StartingStacks::Fixed(vec![1000, 1000]);
+        assert!(action_config.create_starting_stacks(&mut rng).is_err());
+        action_config.starting_stacks = StartingStacks::Uniform { min: 10, max: 20 };
+        assert!(action_config.create_starting_stacks(&mut rng).is_err());
+    }
 }
@@ -1,6 +1,7 @@
 use super::action::Action;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ActionState {
     pub player_to_move: u32,
     pub reward: f32,
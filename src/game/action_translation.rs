@@ -0,0 +1,159 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use super::action::{ActionConfig, ActionType};
+use super::game_error::GameError;
+use super::game_state::GameState;
+
+// Bets of real opponents rarely match the raise sizes of the abstraction. They are played with
+// their real chips (GameState::apply_raise), but the networks see them as one of the action slots.
+
+// Probability of mapping a bet of size x onto the smaller size a rather than the bigger size b.
+// Sizes are fractions of the pot after calling, a <= x <= b. This is the pseudo-harmonic mapping
+// of Ganzfried and Sandholm, it is not exploitable by betting just between two sizes.
+pub fn get_smaller_size_probability(a: f32, b: f32, x: f32) -> f32 {
+    if b <= a {
+        return 1.0;
+    }
+    ((b - x) * (1.0 + a) / ((b - a) * (1.0 + x))).clamp(0.0, 1.0)
+}
+
+// Action slot a raise of the player to move to raise_to chips is seen as. The slot is drawn
+// between the two closest valid raise sizes, a raise for the whole stack is the all-in slot.
+// Error if the abstraction has no raise at all in this state.
+pub fn translate_raise(
+    game_state: &GameState,
+    action_config: &ActionConfig,
+    raise_to: u32,
+    rng: &mut StdRng,
+) -> Result<usize, GameError> {
+    let player = game_state.player_to_move as usize;
+    let legal_actions = game_state.get_legal_actions(action_config)?;
+    let all_in_index = legal_actions.len() - 1;
+    let invalid_action = GameError::InvalidAction {
+        player: game_state.player_to_move,
+        action_index: all_in_index,
+    };
+
+    if raise_to >= game_state.bets[player] + game_state.stacks[player]
+        && legal_actions[all_in_index].is_some()
+    {
+        return Ok(all_in_index);
+    }
+
+    // Size of a raise as a fraction of the pot once the player has called
+    let biggest_bet = game_state.get_biggest_bet();
+    let pot_after_call = (game_state.get_pot() + biggest_bet - game_state.bets[player]) as f32;
+    let get_size = |total_bet: u32| total_bet.saturating_sub(biggest_bet) as f32 / pot_after_call;
+
+    // Valid raise slots and all-in, from the smallest to the biggest
+    let mut sizes: Vec<(usize, f32)> = legal_actions
+        .iter()
+        .enumerate()
+        .filter_map(|(action_index, legal_action)| {
+            legal_action
+                .filter(|legal_action| {
+                    matches!(
+                        legal_action.action_type,
+                        ActionType::Raise | ActionType::AllIn
+                    ) && legal_action.total_bet > biggest_bet
+                })
+                .map(|legal_action| (action_index, get_size(legal_action.total_bet)))
+        })
+        .collect();
+    sizes.sort_by(|a, b| a.1.total_cmp(&b.1));
+    if sizes.is_empty() {
+        return Err(invalid_action);
+    }
+
+    let size = get_size(raise_to);
+    let bigger = sizes
+        .iter()
+        .position(|(_, bigger_size)| *bigger_size >= size);
+    Ok(match bigger {
+        // Bigger than every size, it is seen as the biggest
+        None => sizes[sizes.len() - 1].0,
+        // Smaller than every size or one of them, it is seen as that size
+        Some(0) => sizes[0].0,
+        Some(i) if sizes[i].1 == size => sizes[i].0,
+        Some(i) => {
+            let (smaller_index, smaller_size) = sizes[i - 1];
+            let (bigger_index, bigger_size) = sizes[i];
+            if rng.gen::<f32>() < get_smaller_size_probability(smaller_size, bigger_size, size) {
+                smaller_index
+            } else {
+                bigger_index
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::action::RaiseSize;
+    use rand::SeedableRng;
+
+    const POT: usize = 2;
+    const TWICE_POT: usize = 3;
+    const ALL_IN: usize = 4;
+
+    // Heads-up preflop with blinds of 10 and 20, the button can raise the pot to 50 or twice the
+    // pot to 80. Once the button has called, the pot is 40.
+    fn start_hand() -> (ActionConfig, GameState) {
+        let mut action_config = ActionConfig::new(2, 1000, 20, 9);
+        action_config.preflop_raise_sizes = vec![RaiseSize::Pot(1.0), RaiseSize::Pot(2.0)];
+        let state = GameState::new(2, &[1000, 1000], 1)
+            .deal_next_street(&action_config)
+            .unwrap();
+        (action_config, state)
+    }
+
+    // Share of the draws mapping the raise onto each slot
+    fn get_slot_frequencies(raise_to: u32) -> Vec<f32> {
+        let (action_config, state) = start_hand();
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut counts = vec![0; action_config.get_action_count()];
+        for _ in 0..10_000 {
+            counts[translate_raise(&state, &action_config, raise_to, &mut rng).unwrap()] += 1;
+        }
+        counts
+            .iter()
+            .map(|count| *count as f32 / 10_000.0)
+            .collect()
+    }
+
+    #[test]
+    fn raise_sizes_match_the_slots() {
+        let (action_config, state) = start_hand();
+        let legal_actions = state.get_legal_actions(&action_config).unwrap();
+        assert_eq!(legal_actions[POT].unwrap().total_bet, 50);
+        assert_eq!(legal_actions[TWICE_POT].unwrap().total_bet, 80);
+        assert_eq!(legal_actions[ALL_IN].unwrap().total_bet, 1000);
+    }
+
+    #[test]
+    fn raises_are_drawn_between_the_closest_sizes() {
+        // Smaller than every size or one of them
+        assert_eq!(get_slot_frequencies(45)[POT], 1.0);
+        assert_eq!(get_slot_frequencies(50)[POT], 1.0);
+        assert_eq!(get_slot_frequencies(80)[TWICE_POT], 1.0);
+
+        // Between the pot and twice the pot
+        let frequencies = get_slot_frequencies(65);
+        let smaller_size_probability = get_smaller_size_probability(0.75, 1.5, 1.125);
+        assert!((frequencies[POT] - smaller_size_probability).abs() < 0.02);
+        assert!((frequencies[TWICE_POT] - (1.0 - smaller_size_probability)).abs() < 0.02);
+
+        // Bigger than every raise size, the all-in is the next size
+        let frequencies = get_slot_frequencies(500);
+        let all_in_size = 980.0 / 40.0;
+        let smaller_size_probability = get_smaller_size_probability(1.5, all_in_size, 12.0);
+        assert!((frequencies[TWICE_POT] - smaller_size_probability).abs() < 0.02);
+        assert!((frequencies[ALL_IN] - (1.0 - smaller_size_probability)).abs() < 0.02);
+
+        // The whole stack or more is the all-in
+        assert_eq!(get_slot_frequencies(1000)[ALL_IN], 1.0);
+        assert_eq!(get_slot_frequencies(5000)[ALL_IN], 1.0);
+    }
+}
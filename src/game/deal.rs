@@ -0,0 +1,74 @@
+use super::game_variant::GameVariant;
+use poker::{Card, Rank, Suit};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+const RANKS: [Rank; 13] = [
+    Rank::Two,
+    Rank::Three,
+    Rank::Four,
+    Rank::Five,
+    Rank::Six,
+    Rank::Seven,
+    Rank::Eight,
+    Rank::Nine,
+    Rank::Ten,
+    Rank::Jack,
+    Rank::Queen,
+    Rank::King,
+    Rank::Ace,
+];
+
+const SUITS: [Suit; 4] = [Suit::Spades, Suit::Hearts, Suit::Diamonds, Suit::Clubs];
+
+// Cards of a hand. They are all dealt at the start and do not change during the hand, so they are
+// kept out of the game state and shared by every state of the hand.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Deal {
+    pub game_variant: GameVariant,
+    // Whole board, the cards of a street are only seen from that street
+    #[serde(with = "super::serialization::cards")]
+    pub board: Vec<Card>,
+    #[serde(with = "super::serialization::hands")]
+    pub hands: Vec<Vec<Card>>,
+}
+
+impl Deal {
+    pub fn new(game_variant: GameVariant, player_count: u32, rng: &mut StdRng) -> Deal {
+        let mut deck = Self::create_deck(game_variant);
+        deck.shuffle(rng);
+        Self::from_deck(game_variant, player_count, deck)
+    }
+
+    // Cards are drawn in order, the whole board first then the hole cards of each player
+    pub fn from_deck(game_variant: GameVariant, player_count: u32, mut deck: Vec<Card>) -> Deal {
+        let board = deck
+            .drain(..game_variant.get_board_card_count(game_variant.get_street_count()))
+            .collect();
+        let mut hands = Vec::new();
+        for _ in 0..player_count {
+            hands.push(deck.drain(..game_variant.get_hole_card_count()).collect());
+        }
+
+        Deal {
+            game_variant,
+            board,
+            hands,
+        }
+    }
+
+    // Cards are always generated in the same order, so the shuffle only depends on the rng
+    pub fn create_deck(game_variant: GameVariant) -> Vec<Card> {
+        let mut deck = Vec::new();
+        for rank in RANKS.iter() {
+            for suit in SUITS.iter() {
+                let card = Card::new(*rank, *suit);
+                if game_variant.is_card_used(&card) {
+                    deck.push(card);
+                }
+            }
+        }
+        deck
+    }
+}
@@ -0,0 +1,295 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::action::{Action, ActionConfig, LegalAction};
+use super::action_translation;
+use super::deal::Deal;
+use super::game_error::GameError;
+use super::game_state::{GameState, StateType};
+use super::hand_state::HandState;
+use super::showdown::HandResult;
+use super::tree::Tree;
+use serde::{Deserialize, Serialize};
+
+// What a player sees of the hand
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Observation {
+    // Own hole cards, the board dealt so far and the actions taken, ready for the networks
+    pub hand_state: HandState,
+    // Betting state of the hand, it has no cards so it is public
+    pub game_state: GameState,
+}
+
+// Step by step driver of a hand, for loops that do not go through Tree::traverse. Chance states
+// are dealt inside, so the hand always waits for a player to act or is over.
+pub struct Environment {
+    pub action_config: ActionConfig,
+    // Seat on the button for the next hands, the last seat by default like in a Tree
+    pub button: u32,
+    state: GameState,
+    deal: Deal,
+    history: Vec<Action>,
+    valid_actions_mask: Vec<bool>,
+    // View of the hand for each seat, only its own hole cards are known
+    hand_states: Vec<HandState>,
+    hand_result: Option<HandResult>,
+    // Seeded by reset, used to deal and to translate raises
+    rng: StdRng,
+}
+
+impl Environment {
+    // The first hand is dealt with seed
+    pub fn new(
+        action_config: ActionConfig,
+        seed: u64,
+    ) -> Result<Environment, Box<dyn std::error::Error>> {
+        action_config.validate()?;
+
+        let player_count = action_config.player_count;
+        // Replaced by the hand dealt in reset
+        let mut environment = Environment {
+            button: player_count - 1,
+            state: GameState::new(player_count, &[], 0),
+            deal: Deal {
+                game_variant: action_config.game_variant,
+                board: Vec::new(),
+                hands: Vec::new(),
+            },
+            action_config,
+            history: Vec::new(),
+            valid_actions_mask: Vec::new(),
+            hand_states: Vec::new(),
+            hand_result: None,
+            rng: StdRng::seed_from_u64(seed),
+        };
+        environment.reset(seed)?;
+        Ok(environment)
+    }
+
+    // Start a new hand, the same seed deals the same cards and stacks
+    pub fn reset(&mut self, seed: u64) -> Result<(), Box<dyn std::error::Error>> {
+        self.rng = StdRng::seed_from_u64(seed);
+        let player_count = self.action_config.player_count;
+        let stacks = self.action_config.create_starting_stacks(&mut self.rng)?;
+        self.deal = Deal::new(self.action_config.game_variant, player_count, &mut self.rng);
+        self.state = GameState::new(player_count, &stacks, self.button);
+        self.history.clear();
+        self.hand_states = (0..player_count)
+            .map(|player| HandState::new(player, &self.deal, &self.state))
+            .collect();
+        self.hand_result = None;
+
+        Ok(self.deal_until_decision()?)
+    }
+
+    // View of the hand for a player, the cards of the streets to come are hidden
+    pub fn observe(&self, player: u32) -> Observation {
+        let mut hand_state = self.hand_states[player as usize].clone();
+        let board_card_count = self
+            .action_config
+            .game_variant
+            .get_board_card_count(self.state.street.max(1));
+        hand_state.board.truncate(board_card_count);
+
+        Observation {
+            hand_state,
+            game_state: self.state,
+        }
+    }
+
+    // Valid action slots of the player to move, empty once the hand is over
+    pub fn legal_actions(&self) -> &[bool] {
+        &self.valid_actions_mask
+    }
+
+    // Chips of each action slot of the player to move, None for the invalid ones
+    pub fn legal_action_descriptors(&self) -> Result<Vec<Option<LegalAction>>, GameError> {
+        if self.is_done() {
+            return Ok(Vec::new());
+        }
+        self.state.get_legal_actions(&self.action_config)
+    }
+
+    // None once the hand is over
+    pub fn player_to_move(&self) -> Option<u32> {
+        match self.state.state_type {
+            StateType::Play => Some(self.state.player_to_move as u32),
+            _ => None,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.hand_result.is_some()
+    }
+
+    // The player to move takes the action slot action_index. Returns true if the hand is over.
+    pub fn step(&mut self, action_index: usize) -> Result<bool, GameError> {
+        if self.is_done() {
+            return Err(GameError::Unsupported {
+                state: "terminal",
+                method: "step",
+            });
+        }
+
+        let (child, action) = self.state.apply_action(&self.action_config, action_index)?;
+        self.push_action(child, action, action_index)
+    }
+
+    // The player to move raises to raise_to chips, any size allowed by the rules. The chips are
+    // played as they are, the agents see the raise as the closest raise slots (see
+    // action_translation). Returns true if the hand is over.
+    pub fn step_raise(&mut self, raise_to: u32) -> Result<bool, GameError> {
+        if self.is_done() {
+            return Err(GameError::Unsupported {
+                state: "terminal",
+                method: "step_raise",
+            });
+        }
+
+        let action_index = action_translation::translate_raise(
+            &self.state,
+            &self.action_config,
+            raise_to,
+            &mut self.rng,
+        )?;
+        let (child, action) =
+            self.state
+                .apply_raise(&self.action_config, raise_to, action_index)?;
+        self.push_action(child, action, action_index)
+    }
+
+    fn push_action(
+        &mut self,
+        child: GameState,
+        action: Action,
+        action_index: usize,
+    ) -> Result<bool, GameError> {
+        for hand_state in self.hand_states.iter_mut() {
+            hand_state.action_states.push(Tree::build_action_state(
+                hand_state.traverser,
+                &self.state,
+                &self.valid_actions_mask,
+                action_index,
+                Some(action),
+                false,
+            ));
+        }
+        self.history.push(action);
+        self.state = child;
+
+        self.deal_until_decision()?;
+        Ok(self.is_done())
+    }
+
+    // Rewards of the hand for each player, None until the hand is over
+    pub fn rewards(&self) -> Option<&[f32]> {
+        self.hand_result
+            .as_ref()
+            .map(|hand_result| hand_result.rewards.as_slice())
+    }
+
+    // Chips won or lost by each player, None until the hand is over
+    pub fn chip_results(&self) -> Option<&[i32]> {
+        self.hand_result
+            .as_ref()
+            .map(|hand_result| hand_result.chip_results.as_slice())
+    }
+
+    // Actions taken since the start of the hand
+    pub fn history(&self) -> &[Action] {
+        &self.history
+    }
+
+    // Deal the streets until a player has to act, or pay the pots if the hand is over
+    fn deal_until_decision(&mut self) -> Result<(), GameError> {
+        while self.state.state_type == StateType::Chance {
+            self.state = self.state.deal_next_street(&self.action_config)?;
+        }
+
+        if self.state.state_type == StateType::Play {
+            self.valid_actions_mask = self.state.get_valid_actions_mask(&self.action_config)?;
+            return Ok(());
+        }
+
+        self.valid_actions_mask.clear();
+        let hand_result = self
+            .state
+            .get_hand_result(&self.action_config, &self.deal)?;
+        for hand_state in self.hand_states.iter_mut() {
+            let reward = hand_result.rewards[hand_state.traverser as usize];
+            Tree::update_last_traverser_reward(hand_state, reward, false);
+        }
+        self.hand_result = Some(hand_result);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::action::{ActionType, RaiseSize};
+    use rand::Rng;
+
+    #[test]
+    fn hands_are_played_to_the_end() {
+        let action_config = ActionConfig::new(3, 1000, 20, 9);
+        let mut environment = Environment::new(action_config, 0).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        for seed in 0..300 {
+            environment.button = seed as u32 % 3;
+            environment.reset(seed).unwrap();
+            while let Some(player) = environment.player_to_move() {
+                assert_eq!(environment.observe(player).hand_state.traverser, player);
+                let valid_actions: Vec<usize> = (0..environment.legal_actions().len())
+                    .filter(|action_index| environment.legal_actions()[*action_index])
+                    .collect();
+                let action_index = valid_actions[rng.gen_range(0..valid_actions.len())];
+                environment.step(action_index).unwrap();
+            }
+
+            assert!(environment.is_done());
+            assert!(environment.legal_actions().is_empty());
+            assert!(environment.step(0).is_err());
+            assert_eq!(environment.chip_results().unwrap().iter().sum::<i32>(), 0);
+            assert_eq!(environment.rewards().unwrap().len(), 3);
+        }
+    }
+
+    #[test]
+    fn raises_of_any_size_are_played_with_their_chips() {
+        // Heads-up, the button is the small blind and raises the pot to 50 or all-in
+        let mut action_config = ActionConfig::new(2, 1000, 20, 9);
+        action_config.preflop_raise_sizes = vec![RaiseSize::Pot(1.0)];
+        let mut environment = Environment::new(action_config, 0).unwrap();
+        assert_eq!(environment.player_to_move(), Some(1));
+
+        let legal_actions = environment.legal_action_descriptors().unwrap();
+        let valid_actions: Vec<bool> = legal_actions.iter().map(|a| a.is_some()).collect();
+        assert_eq!(valid_actions, environment.legal_actions());
+        let call = legal_actions[1].unwrap();
+        assert!(matches!(call.action_type, ActionType::Call));
+        assert_eq!((call.amount, call.total_bet), (10, 20));
+        let raise = legal_actions[2].unwrap();
+        assert!(matches!(raise.action_type, ActionType::Raise));
+        assert_eq!((raise.amount, raise.total_bet), (40, 50));
+        assert_eq!(legal_actions[3].unwrap().total_bet, 1000);
+
+        // A raise between the slots is seen as one of them, but its chips are played
+        assert!(!environment.step_raise(65).unwrap());
+        assert!(matches!(
+            environment.history()[0].action_type,
+            ActionType::Raise | ActionType::AllIn
+        ));
+        assert_eq!(environment.observe(0).game_state.bets[1], 65);
+        let call = environment.legal_action_descriptors().unwrap()[1].unwrap();
+        assert_eq!((call.amount, call.total_bet), (45, 65));
+
+        // Raises the rules forbid are an error
+        assert!(environment.step_raise(70).is_err());
+
+        environment.step(0).unwrap();
+        assert!(environment.is_done());
+        assert!(environment.legal_action_descriptors().unwrap().is_empty());
+        assert!(environment.step_raise(100).is_err());
+    }
+}
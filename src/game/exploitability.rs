@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use super::action::ActionConfig;
+use super::deal::Deal;
+use super::game_state::{GameState, StateType};
+use super::hand_state::HandState;
+use super::state_arena::StateArena;
+use super::tree::Tree;
+use crate::agent::Agent;
+
+// Exact best response in the toy games (Kuhn and Leduc). Every deal is enumerated and the betting
+// tree, which does not depend on the cards, is built once in an arena and walked for all the deals
+// together.
+
+// Average of the best response values against the agent from every seat, in chips per hand.
+// It is 0 when the agent plays a Nash equilibrium.
+pub fn get_exploitability(
+    action_config: &ActionConfig,
+    agent: &dyn Agent,
+    device: &candle_core::Device,
+) -> Result<f32, Box<dyn std::error::Error>> {
+    let mut total = 0.0;
+    for player in 0..action_config.player_count {
+        total += get_best_response_value(action_config, agent, device, player)?;
+    }
+    Ok(total / action_config.player_count as f32)
+}
+
+// Chips per hand won by a best response sitting at best_responder, the agent plays the other
+// seats. The button is the last seat, like in a new Tree.
+pub fn get_best_response_value(
+    action_config: &ActionConfig,
+    agent: &dyn Agent,
+    device: &candle_core::Device,
+    best_responder: u32,
+) -> Result<f32, Box<dyn std::error::Error>> {
+    action_config.validate()?;
+    let game_variant = action_config.game_variant;
+    if !game_variant.is_toy_game() {
+        return Err(format!("No exact best response for {:?}", game_variant).into());
+    }
+
+    let player_count = action_config.player_count;
+    let button = player_count - 1;
+    let dealt_card_count = game_variant.get_board_card_count(game_variant.get_street_count())
+        + player_count as usize * game_variant.get_hole_card_count();
+
+    // Every ordered draw of the dealt cards is one equally likely deal
+    let root = GameState::new(
+        player_count,
+        &vec![action_config.buy_in; player_count as usize],
+        button,
+    );
+    let mut deals: Vec<Deal> = Vec::new();
+    let mut hand_states: Vec<Vec<HandState>> = Vec::new();
+    for deck in Deal::create_deck(game_variant)
+        .into_iter()
+        .permutations(dealt_card_count)
+    {
+        let deal = Deal::from_deck(game_variant, player_count, deck);
+        hand_states.push(
+            (0..player_count)
+                .map(|player| HandState::new(player, &deal, &root))
+                .collect(),
+        );
+        deals.push(deal);
+    }
+
+    let mut arena = StateArena::new(root);
+    let reaches = vec![1.0; deals.len()];
+    let best_response = BestResponse {
+        deals: &deals,
+        best_responder,
+        agent,
+        action_config,
+        device,
+    };
+    let values = best_response.get_values(&mut arena, 0, hand_states, &reaches)?;
+
+    Ok(values.iter().sum::<f32>() / values.len() as f32)
+}
+
+// The deals and players of a best response computation
+struct BestResponse<'a> {
+    deals: &'a [Deal],
+    best_responder: u32,
+    agent: &'a dyn Agent,
+    action_config: &'a ActionConfig,
+    device: &'a candle_core::Device,
+}
+
+impl<'a> BestResponse<'a> {
+    // Value of the state at index in the arena for the best responder in each deal. Reaches are
+    // the probabilities that the agent plays to this state in each deal, they weight the deals
+    // when the best responder chooses.
+    fn get_values(
+        &self,
+        arena: &mut StateArena,
+        index: usize,
+        hand_states: Vec<Vec<HandState>>,
+        reaches: &[f32],
+    ) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+        let deals = self.deals;
+        let best_responder = self.best_responder;
+        let action_config = self.action_config;
+        let state = arena.nodes[index].state;
+        match state.state_type {
+            StateType::Terminal => {
+                let mut values = Vec::with_capacity(deals.len());
+                for deal in deals.iter() {
+                    let hand_result = state.get_hand_result(action_config, deal)?;
+                    values.push(hand_result.rewards[best_responder as usize]);
+                }
+                Ok(values)
+            }
+            StateType::Chance => {
+                arena.expand(index, action_config)?;
+                let child = arena.get_child(index, 0)?;
+                self.get_values(arena, child, hand_states, reaches)
+            }
+            StateType::Play => {
+                arena.expand(index, action_config)?;
+                let player = state.player_to_move as u32;
+                let street = state.street;
+                let valid_actions_mask = arena.nodes[index].valid_actions_mask.clone();
+                let keys: Vec<String> = hand_states
+                    .iter()
+                    .map(|deal_hand_states| {
+                        get_information_key(
+                            &deal_hand_states[player as usize],
+                            action_config.game_variant.get_board_card_count(street),
+                        )
+                    })
+                    .collect();
+
+                // Policy of the agent in each deal, deals with the same key look the same to the agent
+                let mut policies: Vec<Vec<f32>> = Vec::new();
+                if player != best_responder {
+                    let mut cache: HashMap<&String, Vec<f32>> = HashMap::new();
+                    for (deal, key) in keys.iter().enumerate() {
+                        if !cache.contains_key(key) {
+                            let policy = self.agent.get_action_probabilities(
+                                &hand_states[deal][player as usize],
+                                &valid_actions_mask,
+                                street,
+                                action_config,
+                                self.device,
+                                true,
+                            )?;
+                            cache.insert(key, policy);
+                        }
+                        policies.push(cache[key].clone());
+                    }
+                }
+
+                // Values of every valid action in each deal
+                let mut action_values: Vec<Option<Vec<f32>>> = vec![None; valid_actions_mask.len()];
+                for (action_index, valid) in valid_actions_mask.iter().enumerate() {
+                    if !*valid {
+                        continue;
+                    }
+
+                    let child = arena.get_child(index, action_index)?;
+                    let action_taken = arena.nodes[child].action;
+                    let mut child_hand_states = hand_states.clone();
+                    for hand_state in child_hand_states.iter_mut().flatten() {
+                        hand_state.action_states.push(Tree::build_action_state(
+                            hand_state.traverser,
+                            &state,
+                            &valid_actions_mask,
+                            action_index,
+                            action_taken,
+                            false,
+                        ));
+                    }
+
+                    let child_reaches: Vec<f32> = if player == best_responder {
+                        reaches.to_vec()
+                    } else {
+                        reaches
+                            .iter()
+                            .zip(policies.iter())
+                            .map(|(reach, policy)| reach * policy[action_index])
+                            .collect()
+                    };
+
+                    action_values[action_index] =
+                        Some(self.get_values(arena, child, child_hand_states, &child_reaches)?);
+                }
+
+                if player != best_responder {
+                    return Ok((0..deals.len())
+                        .map(|deal| {
+                            action_values
+                                .iter()
+                                .enumerate()
+                                .filter_map(|(action_index, values)| {
+                                    values
+                                        .as_ref()
+                                        .map(|values| policies[deal][action_index] * values[deal])
+                                })
+                                .sum()
+                        })
+                        .collect());
+                }
+
+                // The best responder takes the best action for each of its information sets
+                let mut best_actions: HashMap<&String, usize> = HashMap::new();
+                for key in keys.iter() {
+                    if best_actions.contains_key(key) {
+                        continue;
+                    }
+
+                    let mut best_action = 0;
+                    let mut best_value = f32::NEG_INFINITY;
+                    for (action_index, values) in action_values.iter().enumerate() {
+                        if let Some(values) = values {
+                            let value: f32 = (0..deals.len())
+                                .filter(|deal| keys[*deal] == *key)
+                                .map(|deal| reaches[deal] * values[deal])
+                                .sum();
+                            if value > best_value {
+                                best_value = value;
+                                best_action = action_index;
+                            }
+                        }
+                    }
+                    best_actions.insert(key, best_action);
+                }
+
+                Ok((0..deals.len())
+                    .map(|deal| action_values[best_actions[&keys[deal]]].as_ref().unwrap()[deal])
+                    .collect())
+            }
+        }
+    }
+}
+
+// What the player knows besides the actions: his cards and the board dealt so far
+fn get_information_key(hand_state: &HandState, board_card_count: usize) -> String {
+    hand_state
+        .hand
+        .iter()
+        .chain(hand_state.board[..board_card_count].iter())
+        .map(|card| card.rank_suit_string())
+        .join("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::agent_random::AgentRandom;
+    use poker::Rank;
+    use rand::rngs::StdRng;
+    use rand::Rng;
+
+    // Nash equilibrium of Kuhn poker, seat 0 acts first. With J it bets ALPHA, with Q it checks
+    // and calls a bet ALPHA + 1/3, with K it bets 3 ALPHA. Seat 1 bets J 1/3 and K after a check,
+    // calls a bet with Q 1/3 and with K.
+    struct AgentKuhn {}
+
+    const ALPHA: f32 = 0.2;
+
+    impl Agent for AgentKuhn {
+        fn choose_action(
+            &self,
+            hand_state: &HandState,
+            valid_actions_mask: &[bool],
+            street: u8,
+            action_config: &ActionConfig,
+            device: &candle_core::Device,
+            no_invalid: bool,
+            rng: &mut StdRng,
+        ) -> Result<usize, Box<dyn std::error::Error>> {
+            let probabilities = self.get_action_probabilities(
+                hand_state,
+                valid_actions_mask,
+                street,
+                action_config,
+                device,
+                no_invalid,
+            )?;
+            let mut random: f32 = rng.gen();
+            for (action_index, probability) in probabilities.iter().enumerate() {
+                if random < *probability {
+                    return Ok(action_index);
+                }
+                random -= probability;
+            }
+            Ok(1)
+        }
+
+        fn get_action_probabilities(
+            &self,
+            hand_state: &HandState,
+            valid_actions_mask: &[bool],
+            _street: u8,
+            _action_config: &ActionConfig,
+            _device: &candle_core::Device,
+            _no_invalid: bool,
+        ) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+            let card = match hand_state.hand[0].rank() {
+                Rank::Jack => 0,
+                Rank::Queen => 1,
+                _ => 2,
+            };
+            let actions: Vec<usize> = hand_state
+                .action_states
+                .iter()
+                .map(|action_state| action_state.action_taken_index)
+                .collect();
+
+            // Probability to bet or call, the slots are fold, check or call, bet and all-in
+            let (probability, aggressive_index, passive_index) = match actions[..] {
+                [] => ([ALPHA, 0.0, 3.0 * ALPHA][card], 2, 1),
+                [1] => ([1.0 / 3.0, 0.0, 1.0][card], 2, 1),
+                [2] => ([0.0, 1.0 / 3.0, 1.0][card], 1, 0),
+                _ => ([0.0, ALPHA + 1.0 / 3.0, 1.0][card], 1, 0),
+            };
+            let mut probabilities = vec![0.0; valid_actions_mask.len()];
+            probabilities[aggressive_index] = probability;
+            probabilities[passive_index] = 1.0 - probability;
+            Ok(probabilities)
+        }
+    }
+
+    #[test]
+    fn kuhn_equilibrium_is_not_exploitable() {
+        let action_config = ActionConfig::new_kuhn();
+        let device = candle_core::Device::Cpu;
+        let agent = AgentKuhn {};
+
+        // The game is worth -1/18 to the first player
+        let first = get_best_response_value(&action_config, &agent, &device, 0).unwrap();
+        let second = get_best_response_value(&action_config, &agent, &device, 1).unwrap();
+        assert!((first + 1.0 / 18.0).abs() < 1e-5, "{}", first);
+        assert!((second - 1.0 / 18.0).abs() < 1e-5, "{}", second);
+        assert!(
+            get_exploitability(&action_config, &agent, &device)
+                .unwrap()
+                .abs()
+                < 1e-5
+        );
+    }
+
+    #[test]
+    fn random_agent_is_exploitable() {
+        let device = candle_core::Device::Cpu;
+        for action_config in [ActionConfig::new_kuhn(), ActionConfig::new_leduc()] {
+            let exploitability =
+                get_exploitability(&action_config, &AgentRandom {}, &device).unwrap();
+            assert!(exploitability > 0.1, "{}", exploitability);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt;
+
+// Illegal situations met while building or walking the game tree. They are returned instead of
+// panicking, so a worker can log or skip the hand and keep going.
+#[derive(Debug)]
+pub enum GameError {
+    // The method does not exist for this type of state, like the children of a terminal state
+    Unsupported {
+        state: &'static str,
+        method: &'static str,
+    },
+    // The state was never created, or was already taken from its parent
+    MissingState,
+    // The action chosen can not be played by the player to move
+    InvalidAction {
+        player: i32,
+        action_index: usize,
+    },
+    // Every action slot is invalid for the player to move
+    NoValidAction {
+        player: i32,
+    },
+    // Someone raised but no player is left to answer the raise
+    NoPlayerAfterRaise {
+        player: i32,
+        street: u8,
+    },
+    // Nobody can act on the new street, but nobody is all-in either
+    NoPlayerToAct {
+        street: u8,
+    },
+    // The evaluator could not rank the cards at showdown, like a duplicated card
+    InvalidHand {
+        cards: String,
+        error: String,
+    },
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::Unsupported { state, method } => {
+                write!(f, "{} is not supported by a {} state", method, state)
+            }
+            GameError::MissingState => write!(f, "State is None"),
+            GameError::InvalidAction {
+                player,
+                action_index,
+            } => write!(f, "Invalid action {} for player {}", action_index, player),
+            GameError::NoValidAction { player } => {
+                write!(f, "Not enough valid actions for player {}", player)
+            }
+            GameError::NoPlayerAfterRaise { player, street } => write!(
+                f,
+                "Player {} raised on street {} but there is noone left to play next",
+                player, street
+            ),
+            GameError::NoPlayerToAct { street } => write!(
+                f,
+                "No players left to act on street {} but nobody is all-in",
+                street
+            ),
+            GameError::InvalidHand { cards, error } => {
+                write!(f, "Couldn't evaluate hand {}: {}", cards, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
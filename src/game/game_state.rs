@@ -0,0 +1,910 @@
+use super::action::{Action, ActionConfig, ActionType, BettingStructure, LegalAction};
+use super::game_error::GameError;
+use serde::{Deserialize, Serialize};
+
+// Most players at a table, the game state keeps the seats in fixed-size arrays
+pub const MAX_PLAYER_COUNT: usize = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum StateType {
+    Play,
+    Terminal,
+    Chance,
+}
+
+// Betting state of a hand, without the cards (see Deal) nor the history of the actions.
+// It is small and copyable: a rollout moves from state to state with apply_action and
+// deal_next_street without any allocation, and a full tree can keep its states in a StateArena.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct GameState {
+    pub state_type: StateType,
+    pub player_count: u32,
+    pub button: u32,
+    pub street: u8,
+    pub stacks: [u32; MAX_PLAYER_COUNT],
+    // Bets are accumulated over the whole hand
+    pub bets: [u32; MAX_PLAYER_COUNT],
+    // Antes are dead money, they are in the pot but do not count towards calling a bet
+    pub antes: [u32; MAX_PLAYER_COUNT],
+    pub is_player_in: [bool; MAX_PLAYER_COUNT],
+    pub is_all_in: [bool; MAX_PLAYER_COUNT],
+    pub players_in_hand: u32,
+    pub player_to_move: i32,
+    pub last_player: i32,
+    // Last full raise increment of the street, the next raise must be at least this much
+    pub min_raise: u32,
+    // Biggest bet when each player last acted in the street, None if he did not act yet
+    pub bet_when_acted: [Option<u32>; MAX_PLAYER_COUNT],
+    // Bets and full raises made in the street, the blinds count as the first bet preflop
+    pub raise_count: u8,
+    // Actions made in the street, and if one of them is an all-in
+    pub street_action_count: u8,
+    pub street_has_all_in: bool,
+    // Street of the last action, 0 if nobody acted. Action closed on that street.
+    pub last_action_street: u8,
+}
+
+impl GameState {
+    // Start of a hand, before the forced bets are posted. Seats with an empty stack sit out the
+    // hand, a button on such a seat moves to the next seat with chips.
+    pub fn new(player_count: u32, stacks: &[u32], button: u32) -> GameState {
+        let mut state_stacks = [0; MAX_PLAYER_COUNT];
+        state_stacks[..stacks.len()].copy_from_slice(stacks);
+        let mut is_player_in = [false; MAX_PLAYER_COUNT];
+        for player in 0..player_count as usize {
+            is_player_in[player] = state_stacks[player] > 0;
+        }
+        let players_in_hand = is_player_in.iter().filter(|in_hand| **in_hand).count() as u32;
+        let button = (0..player_count)
+            .map(|i| (button + i) % player_count)
+            .find(|player| is_player_in[*player as usize])
+            .unwrap_or(button % player_count);
+
+        GameState {
+            state_type: StateType::Chance,
+            player_count,
+            button,
+            street: 0,
+            stacks: state_stacks,
+            bets: [0; MAX_PLAYER_COUNT],
+            antes: [0; MAX_PLAYER_COUNT],
+            is_player_in,
+            is_all_in: [false; MAX_PLAYER_COUNT],
+            players_in_hand,
+            player_to_move: -1,
+            last_player: -1,
+            min_raise: 0,
+            bet_when_acted: [None; MAX_PLAYER_COUNT],
+            raise_count: 0,
+            street_action_count: 0,
+            street_has_all_in: false,
+            last_action_street: 0,
+        }
+    }
+
+    // Chips put in the pot by a player during the hand
+    pub fn get_contribution(&self, player: u32) -> u32 {
+        self.bets[player as usize] + self.antes[player as usize]
+    }
+
+    pub fn get_pot(&self) -> u32 {
+        (0..self.player_count)
+            .map(|i| self.get_contribution(i))
+            .sum()
+    }
+
+    // Bets are accumulated over the whole hand, so the stack at the start of the hand is both
+    pub fn get_starting_stack(&self, player: u32) -> u32 {
+        self.stacks[player as usize] + self.get_contribution(player)
+    }
+
+    // Seats that started the hand without chips, they post nothing and are dealt out
+    pub fn is_sitting_out(&self, player: u32) -> bool {
+        self.get_starting_stack(player) == 0
+    }
+
+    // Next seat after from that is not sitting out
+    fn get_next_seated_player(&self, from: u32) -> u32 {
+        (1..=self.player_count)
+            .map(|i| (from + i) % self.player_count)
+            .find(|player| !self.is_sitting_out(*player))
+            .unwrap_or(from)
+    }
+
+    pub fn get_small_blind_player(&self) -> u32 {
+        // Heads-up, the button posts the small blind
+        let seated_count = (0..self.player_count)
+            .filter(|player| !self.is_sitting_out(*player))
+            .count();
+        if seated_count == 2 {
+            self.button
+        } else {
+            self.get_next_seated_player(self.button)
+        }
+    }
+
+    pub fn get_big_blind_player(&self) -> u32 {
+        self.get_next_seated_player(self.get_small_blind_player())
+    }
+
+    // Players still in the hand who are not all-in
+    pub fn can_act(&self, player: u32) -> bool {
+        self.is_player_in[player as usize] && !self.is_all_in[player as usize]
+    }
+
+    pub fn get_biggest_bet(&self) -> u32 {
+        self.bets[..self.player_count as usize]
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Next player who can act after from, up to last (included). -1 if there is none
+    fn get_next_player(&self, from: i32, last: i32) -> i32 {
+        let player_count = self.player_count as i32;
+        let mut i = (from + 1) % player_count;
+        let end = (last + 1) % player_count;
+        while i != end {
+            if self.can_act(i as u32) {
+                return i;
+            }
+            i = (i + 1) % player_count;
+        }
+
+        -1
+    }
+
+    // Last player who can act before coming back to the player that raised
+    fn get_last_player(&self, player_that_raised: i32) -> i32 {
+        let player_count = self.player_count as i32;
+        let mut last: i32 = -1;
+        let mut i = (player_that_raised + 1) % player_count;
+        while i != player_that_raised {
+            if self.can_act(i as u32) {
+                last = i;
+            }
+            i = (i + 1) % player_count;
+        }
+        last
+    }
+
+    fn get_number_of_players_that_need_to_act(&self) -> u32 {
+        (0..self.player_count).filter(|i| self.can_act(*i)).count() as u32
+    }
+
+    // Transition of a chance state: the forced bets are posted preflop, then each street deals
+    // its cards. The next state is the first decision, or another deal when nobody can bet.
+    pub fn deal_next_street(&self, action_config: &ActionConfig) -> Result<GameState, GameError> {
+        if self.state_type != StateType::Chance {
+            return Err(GameError::Unsupported {
+                state: self.get_type_name(),
+                method: "deal_next_street",
+            });
+        }
+
+        let mut state = *self;
+        state.player_to_move = -1;
+        state.last_player = -1;
+        state.street += 1;
+        state.min_raise = action_config.get_min_raise(state.street);
+        state.bet_when_acted = [None; MAX_PLAYER_COUNT];
+        state.raise_count = 0;
+        state.street_action_count = 0;
+        state.street_has_all_in = false;
+
+        let player_count = self.player_count as usize;
+        if state.street == 1 {
+            let sb_player = self.get_small_blind_player() as usize;
+            let bb_player = self.get_big_blind_player() as usize;
+            let utg_player = self.get_next_seated_player(bb_player as u32) as usize;
+
+            // Post antes, they are dead money. Seats sitting out post nothing.
+            for i in 0..player_count {
+                if self.is_player_in[i] {
+                    state.antes[i] = action_config.ante;
+                }
+            }
+            state.antes[bb_player] += action_config.big_blind_ante;
+
+            // Post blinds
+            if action_config.has_blinds() {
+                state.bets[sb_player] = action_config.small_blind;
+                state.bets[bb_player] = action_config.big_blind;
+                state.raise_count = 1;
+            }
+
+            // Preflop, first to act is after the big blind (or the straddle), last to act is the big blind (or the straddle).
+            // Without blinds, it is the same as postflop
+            if !action_config.has_blinds() {
+                state.player_to_move = ((self.button as usize + 1) % player_count) as i32;
+                state.last_player = self.button as i32;
+            } else if action_config.has_straddle() {
+                state.bets[utg_player] = action_config.get_straddle();
+                state.min_raise = action_config.get_straddle();
+                state.raise_count = 2;
+                state.player_to_move = ((utg_player + 1) % player_count) as i32;
+                state.last_player = utg_player as i32;
+            } else {
+                state.player_to_move = utg_player as i32;
+                state.last_player = bb_player as i32;
+            }
+
+            // A player who can not cover his forced bets posts his whole stack, the ante first,
+            // and is all-in
+            for i in (0..player_count).filter(|i| self.is_player_in[*i]) {
+                let stack = state.stacks[i];
+                if state.bets[i] + state.antes[i] >= stack {
+                    state.antes[i] = state.antes[i].min(stack);
+                    state.bets[i] = stack - state.antes[i];
+                    state.is_all_in[i] = true;
+                }
+                state.stacks[i] -= state.bets[i] + state.antes[i];
+            }
+
+            // All-in players and seats sitting out are skipped, the first to act is the next one
+            // who can act and the last to act the previous one
+            if state.player_to_move >= 0 {
+                let first = state.player_to_move as usize;
+                let last = state.last_player as usize;
+                let next = (0..player_count)
+                    .map(|i| (first + i) % player_count)
+                    .find(|player| state.can_act(*player as u32));
+                let previous = (0..player_count)
+                    .map(|i| (last + player_count - i) % player_count)
+                    .find(|player| state.can_act(*player as u32));
+                state.player_to_move = next.map_or(-1, |player| player as i32);
+                state.last_player = previous.map_or(-1, |player| player as i32);
+            }
+        } else {
+            // Postflop, first to act is the first active player after the button, last to act is the last active player
+            for i in 1..=self.player_count {
+                let player = (self.button + i) % self.player_count;
+                if self.is_player_in[player as usize] && self.stacks[player as usize] > 0 {
+                    state.player_to_move = player as i32;
+                    break;
+                }
+            }
+
+            if state.player_to_move >= 0 {
+                state.last_player = state.get_last_player(state.player_to_move);
+            }
+        }
+
+        // Players who can still act, all-in players are out of the betting
+        let players_that_can_act: Vec<usize> = (0..player_count)
+            .filter(|player| state.can_act(*player as u32))
+            .collect();
+        let all_in_count = state.is_all_in[..player_count]
+            .iter()
+            .filter(|all_in| **all_in)
+            .count();
+
+        // Preflop, a single player who can act still has to call or fold if the blinds of the
+        // all-in players are bigger than his
+        if state.street == 1
+            && players_that_can_act.len() == 1
+            && state.bets[players_that_can_act[0]] < state.get_biggest_bet()
+        {
+            state.player_to_move = players_that_can_act[0] as i32;
+            state.last_player = players_that_can_act[0] as i32;
+            state.state_type = StateType::Play;
+            return Ok(state);
+        }
+
+        let street_count = action_config.game_variant.get_street_count();
+        if players_that_can_act.len() >= 2 && state.street <= street_count {
+            state.state_type = StateType::Play;
+        } else {
+            // At most one player has chips left, the others are all-in, so we deal until showdown
+            if all_in_count == 0 {
+                return Err(GameError::NoPlayerToAct {
+                    street: state.street,
+                });
+            }
+
+            state.state_type = if state.street <= street_count {
+                StateType::Chance
+            } else {
+                StateType::Terminal
+            };
+        }
+
+        Ok(state)
+    }
+
+    // One entry per action slot: fold, call, the raise slots and all-in
+    pub fn get_valid_actions_mask(
+        &self,
+        action_config: &ActionConfig,
+    ) -> Result<Vec<bool>, GameError> {
+        let mut valid_actions_mask = Vec::with_capacity(action_config.get_action_count());
+        for action_index in 0..action_config.get_action_count() {
+            valid_actions_mask.push(self.try_action(action_config, action_index)?.is_some());
+        }
+
+        if !valid_actions_mask.iter().any(|valid| *valid) {
+            return Err(GameError::NoValidAction {
+                player: self.player_to_move,
+            });
+        }
+
+        Ok(valid_actions_mask)
+    }
+
+    // A raise of any size by the player to move, like the bets of real opponents that are off
+    // the abstraction. The state keeps the real chips, the raise is recorded as the slot
+    // action_index (see action_translation). A raise_to that covers the stack is an all-in.
+    pub fn apply_raise(
+        &self,
+        action_config: &ActionConfig,
+        raise_to: u32,
+        action_index: usize,
+    ) -> Result<(GameState, Action), GameError> {
+        if self.state_type != StateType::Play {
+            return Err(GameError::Unsupported {
+                state: self.get_type_name(),
+                method: "apply_raise",
+            });
+        }
+
+        let invalid_action = GameError::InvalidAction {
+            player: self.player_to_move,
+            action_index,
+        };
+        let action_count = action_config.get_action_count();
+        if action_index < 2 || action_index >= action_count {
+            return Err(invalid_action);
+        }
+
+        // Only the rules of the game apply, not the limits of the abstraction
+        let biggest_bet = self.get_biggest_bet();
+        let child = if raise_to >= self.get_to_move_bet() + self.get_to_move_stack() {
+            self.create_all_in(action_config, biggest_bet)
+        } else {
+            let is_allowed = match action_config.betting_structure {
+                BettingStructure::NoLimit => true,
+                BettingStructure::PotLimit => {
+                    raise_to <= self.get_pot_limit_raise_to(self.get_pot(), biggest_bet)
+                }
+                BettingStructure::FixedLimit { raise_cap, .. } => {
+                    self.raise_count < raise_cap
+                        && raise_to == biggest_bet + action_config.get_limit_bet(self.street)
+                }
+            };
+            // A raise recorded as the all-in slot has no raise slot
+            let raise_index = if action_index == action_count - 1 {
+                -1
+            } else {
+                (action_index - 2) as i8
+            };
+            if is_allowed {
+                self.create_raise(biggest_bet, raise_to, raise_index)?
+            } else {
+                None
+            }
+        };
+
+        child.ok_or(invalid_action)
+    }
+
+    // Chips of each action slot, None for the invalid ones
+    pub fn get_legal_actions(
+        &self,
+        action_config: &ActionConfig,
+    ) -> Result<Vec<Option<LegalAction>>, GameError> {
+        let pot = self.get_pot();
+        let player = self.player_to_move as usize;
+        let mut legal_actions = Vec::with_capacity(action_config.get_action_count());
+        for action_index in 0..action_config.get_action_count() {
+            legal_actions.push(self.try_action(action_config, action_index)?.map(
+                |(state, action)| {
+                    let amount = state.bets[player] - self.bets[player];
+                    LegalAction {
+                        action_type: action.action_type,
+                        amount,
+                        total_bet: state.bets[player],
+                        pot_fraction: if pot > 0 {
+                            amount as f32 / pot as f32
+                        } else {
+                            0.0
+                        },
+                    }
+                },
+            ));
+        }
+        Ok(legal_actions)
+    }
+
+    // Transition of a play state: the player to move takes the action slot action_index
+    pub fn apply_action(
+        &self,
+        action_config: &ActionConfig,
+        action_index: usize,
+    ) -> Result<(GameState, Action), GameError> {
+        self.try_action(action_config, action_index)?
+            .ok_or(GameError::InvalidAction {
+                player: self.player_to_move,
+                action_index,
+            })
+    }
+
+    // Next state and action taken for an action slot, None if the slot can not be played
+    fn try_action(
+        &self,
+        action_config: &ActionConfig,
+        action_index: usize,
+    ) -> Result<Option<(GameState, Action)>, GameError> {
+        if self.state_type != StateType::Play {
+            return Err(GameError::Unsupported {
+                state: self.get_type_name(),
+                method: "apply_action",
+            });
+        }
+
+        // Antes are dead money, they are in the pot but not in the bets to match
+        let pot = self.get_pot();
+        let biggest_bet = self.get_biggest_bet();
+        let action_count = action_config.get_action_count();
+        Ok(match action_index {
+            0 => self.fold(action_config, biggest_bet),
+            1 => self.call(action_config, biggest_bet),
+            index if index == action_count - 1 => self.all_in(action_config, biggest_bet),
+            index if index < action_count => {
+                let slot = index - 2;
+                match self.get_raise_to(action_config, pot, biggest_bet, slot) {
+                    Some(raise_to) => self.raise(action_config, biggest_bet, slot, raise_to)?,
+                    None => None,
+                }
+            }
+            _ => None,
+        })
+    }
+
+    // Copy of the state with the action recorded for the player to move
+    fn create_action_state(&self, action_type: ActionType, raise_index: i8) -> (GameState, Action) {
+        let action = Action {
+            action_type,
+            raise_index,
+            player_index: self.player_to_move,
+            street: self.street,
+        };
+
+        let mut state = *self;
+        state.street_action_count += 1;
+        state.last_action_street = self.street;
+        if matches!(action.action_type, ActionType::AllIn) {
+            state.street_has_all_in = true;
+            state.is_all_in[self.player_to_move as usize] = true;
+        }
+        (state, action)
+    }
+
+    // After an action, the hand goes on with the next player, the next street or the showdown
+    fn set_next_state_type(&mut self, street_count: u8) {
+        self.state_type = if self.players_in_hand == 1 {
+            StateType::Terminal
+        } else if self.player_to_move != -1 {
+            StateType::Play
+        } else if self.street != street_count {
+            StateType::Chance
+        } else {
+            StateType::Terminal
+        };
+    }
+
+    fn get_to_move_bet(&self) -> u32 {
+        self.bets[self.player_to_move as usize]
+    }
+
+    fn get_to_move_stack(&self) -> u32 {
+        self.stacks[self.player_to_move as usize]
+    }
+
+    // Total bet reached with a raise slot, None if the slot can not be used in this street
+    fn get_raise_to(
+        &self,
+        action_config: &ActionConfig,
+        pot: u32,
+        biggest_bet: u32,
+        slot: usize,
+    ) -> Option<u32> {
+        let street = self.street;
+        match action_config.betting_structure {
+            BettingStructure::NoLimit => {
+                action_config
+                    .get_raise_sizes(street)
+                    .get(slot)
+                    .map(|raise_size| {
+                        raise_size.get_raise_to(pot, biggest_bet, action_config.big_blind)
+                    })
+            }
+            // Sizes over the pot are not allowed
+            BettingStructure::PotLimit => action_config
+                .get_raise_sizes(street)
+                .get(slot)
+                .map(|raise_size| {
+                    raise_size.get_raise_to(pot, biggest_bet, action_config.big_blind)
+                })
+                .filter(|raise_to| *raise_to <= self.get_pot_limit_raise_to(pot, biggest_bet)),
+            BettingStructure::FixedLimit { raise_cap, .. } => {
+                if self.raise_count >= raise_cap {
+                    None
+                } else {
+                    Some(biggest_bet + action_config.get_limit_bet(street))
+                }
+            }
+        }
+    }
+
+    fn fold(&self, action_config: &ActionConfig, biggest_bet: u32) -> Option<(GameState, Action)> {
+        if biggest_bet <= self.get_to_move_bet() {
+            return None;
+        }
+
+        let (mut state, action) = self.create_action_state(ActionType::Fold, -1);
+        state.is_player_in[self.player_to_move as usize] = false;
+        state.players_in_hand -= 1;
+        state.player_to_move = state.get_next_player(self.player_to_move, self.last_player);
+        state.set_next_state_type(action_config.game_variant.get_street_count());
+        Some((state, action))
+    }
+
+    fn call(&self, action_config: &ActionConfig, biggest_bet: u32) -> Option<(GameState, Action)> {
+        let to_call = biggest_bet - self.get_to_move_bet();
+        if to_call >= self.get_to_move_stack() {
+            return None;
+        }
+
+        let (mut state, action) = self.create_action_state(ActionType::Call, -1);
+        let player = self.player_to_move as usize;
+        state.bets[player] += to_call;
+        state.stacks[player] -= to_call;
+        state.bet_when_acted[player] = Some(biggest_bet);
+        state.player_to_move = state.get_next_player(self.player_to_move, self.last_player);
+        state.set_next_state_type(action_config.game_variant.get_street_count());
+        Some((state, action))
+    }
+
+    fn raise(
+        &self,
+        action_config: &ActionConfig,
+        biggest_bet: u32,
+        slot: usize,
+        raise_to: u32,
+    ) -> Result<Option<(GameState, Action)>, GameError> {
+        // No raise if we already more than max_action - player_count actions in the round
+        let max_actions = action_config.max_actions_per_street as usize;
+        if self.street_action_count as usize
+            > max_actions.saturating_sub(self.player_count as usize)
+        {
+            return Ok(None);
+        }
+
+        // Only no-limit always has an all-in to fall back on, otherwise a raise is fine even if
+        // it leaves a short stack
+        let actual_bet = raise_to.saturating_sub(self.get_to_move_bet());
+        let stack_left: i32 = self.get_to_move_stack() as i32 - actual_bet as i32;
+        if action_config.is_no_limit()
+            && (stack_left as f32)
+                < (action_config.commited_to_pot_percentage as f32
+                    * self.get_starting_stack(self.player_to_move as u32) as f32
+                    / 100.0)
+        {
+            return Ok(None);
+        }
+
+        self.create_raise(biggest_bet, raise_to, slot as i8)
+    }
+
+    // Raise allowed by the rules of the game, the limits of the abstraction are checked before
+    fn create_raise(
+        &self,
+        biggest_bet: u32,
+        raise_to: u32,
+        raise_index: i8,
+    ) -> Result<Option<(GameState, Action)>, GameError> {
+        // No raise if every other player is all-in, nobody could call it. No raise either if the
+        // action was not reopened by a full raise since the player last acted
+        if self.get_number_of_players_that_need_to_act() < 2
+            || !self.is_action_reopened(biggest_bet)
+        {
+            return Ok(None);
+        }
+
+        // A raise for the whole stack is an all-in
+        let raise = raise_to.saturating_sub(biggest_bet);
+        let actual_bet = raise_to.saturating_sub(self.get_to_move_bet());
+        if raise < self.min_raise || actual_bet >= self.get_to_move_stack() {
+            return Ok(None);
+        }
+
+        // Valid raise, it is a full raise so it sets the next minimum raise
+        let (mut state, action) = self.create_action_state(ActionType::Raise, raise_index);
+        let player = self.player_to_move as usize;
+        state.bets[player] += actual_bet;
+        state.stacks[player] -= actual_bet;
+        state.min_raise = raise;
+        state.raise_count += 1;
+        state.bet_when_acted[player] = Some(state.bets[player]);
+
+        state.last_player = state.get_last_player(self.player_to_move);
+        state.player_to_move = state.get_next_player(self.player_to_move, state.last_player);
+
+        if state.player_to_move == -1 {
+            return Err(GameError::NoPlayerAfterRaise {
+                player: self.player_to_move,
+                street: self.street,
+            });
+        }
+        state.state_type = StateType::Play;
+        Ok(Some((state, action)))
+    }
+
+    fn all_in(
+        &self,
+        action_config: &ActionConfig,
+        biggest_bet: u32,
+    ) -> Option<(GameState, Action)> {
+        // No raise if there is no all-in and we already have more than max_action - player_count actions in the round
+        let max_actions = action_config.max_actions_per_street as usize;
+        if !self.street_has_all_in
+            && self.street_action_count as usize
+                > max_actions.saturating_sub(self.player_count as usize)
+        {
+            return None;
+        }
+
+        self.create_all_in(action_config, biggest_bet)
+    }
+
+    // All-in allowed by the rules of the game, the limits of the abstraction are checked before
+    fn create_all_in(
+        &self,
+        action_config: &ActionConfig,
+        biggest_bet: u32,
+    ) -> Option<(GameState, Action)> {
+        // Going all-in for more than a call is a raise, it needs the action to be open
+        let to_call = biggest_bet - self.get_to_move_bet();
+        if self.get_to_move_stack() > to_call && !self.is_action_reopened(biggest_bet) {
+            return None;
+        }
+
+        // In fixed-limit, all-in is only there for a player who can not call or put in a full
+        // raise, and it can not go over the raise cap
+        if let BettingStructure::FixedLimit { raise_cap, .. } = action_config.betting_structure {
+            let limit_bet = action_config.get_limit_bet(self.street);
+            if self.get_to_move_stack() > to_call
+                && (self.get_to_move_stack() > to_call + limit_bet || self.raise_count >= raise_cap)
+            {
+                return None;
+            }
+        }
+
+        // In pot-limit, all-in is only possible if the stack is within the pot limit
+        if matches!(action_config.betting_structure, BettingStructure::PotLimit)
+            && self.get_to_move_bet() + self.get_to_move_stack()
+                > self.get_pot_limit_raise_to(self.get_pot(), biggest_bet)
+        {
+            return None;
+        }
+
+        if self.get_to_move_stack() == 0 {
+            return None;
+        }
+
+        let (mut state, action) = self.create_action_state(ActionType::AllIn, -1);
+        let player = self.player_to_move as usize;
+        state.bets[player] += self.get_to_move_stack();
+        state.stacks[player] = 0;
+
+        let new_bet = state.bets[player];
+        let raise = new_bet.saturating_sub(biggest_bet);
+        state.bet_when_acted[player] = Some(new_bet.max(biggest_bet));
+
+        // Only a full raise sets the next minimum raise. An incomplete raise still needs an
+        // answer from the other players, but does not reopen the action for those who acted
+        if raise >= self.min_raise {
+            state.min_raise = raise;
+            state.raise_count += 1;
+        }
+
+        // An all-in for less than a call does not change who is last to act
+        if raise > 0 {
+            state.last_player = state.get_last_player(self.player_to_move);
+        }
+        state.player_to_move = state.get_next_player(self.player_to_move, state.last_player);
+        state.set_next_state_type(action_config.game_variant.get_street_count());
+        Some((state, action))
+    }
+
+    // Biggest total bet in pot-limit: call, then raise by the pot after the call
+    fn get_pot_limit_raise_to(&self, pot: u32, biggest_bet: u32) -> u32 {
+        let to_call = biggest_bet - self.get_to_move_bet();
+        biggest_bet + pot + to_call
+    }
+
+    // A player who already acted in the street can only raise again if he faces at least a full
+    // raise since then. Incomplete all-in raises do not reopen the action.
+    fn is_action_reopened(&self, biggest_bet: u32) -> bool {
+        match self.bet_when_acted[self.player_to_move as usize] {
+            Some(bet) => biggest_bet >= bet + self.min_raise,
+            None => true,
+        }
+    }
+
+    pub fn get_type_name(&self) -> &'static str {
+        match self.state_type {
+            StateType::Play => "play",
+            StateType::Chance => "chance",
+            StateType::Terminal => "terminal",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // No raise slot, raises of any size are played with apply_raise on the all-in slot
+    const ALL_IN: usize = 2;
+
+    // Preflop with blinds of 10 and 20, the last seat is on the button
+    fn start_hand(action_config: &ActionConfig, stacks: &[u32]) -> GameState {
+        let player_count = stacks.len() as u32;
+        GameState::new(player_count, stacks, player_count - 1)
+            .deal_next_street(action_config)
+            .unwrap()
+    }
+
+    fn raise(action_config: &ActionConfig, state: &GameState, raise_to: u32) -> GameState {
+        state
+            .apply_raise(action_config, raise_to, ALL_IN)
+            .unwrap()
+            .0
+    }
+
+    fn call(action_config: &ActionConfig, state: &GameState) -> GameState {
+        state.apply_action(action_config, 1).unwrap().0
+    }
+
+    fn can_raise(action_config: &ActionConfig, state: &GameState) -> bool {
+        state.get_valid_actions_mask(action_config).unwrap()[ALL_IN]
+    }
+
+    #[test]
+    fn min_raise_is_the_last_full_raise() {
+        let action_config = ActionConfig::new(4, 1000, 20, 9);
+        let state = start_hand(&action_config, &[1000; 4]);
+        assert_eq!(state.player_to_move, 2);
+        assert_eq!(state.min_raise, 20);
+        assert!(state.apply_raise(&action_config, 39, ALL_IN).is_err());
+
+        let state = raise(&action_config, &state, 60);
+        assert_eq!(state.min_raise, 40);
+        assert!(state.apply_raise(&action_config, 99, ALL_IN).is_err());
+
+        let state = raise(&action_config, &state, 100);
+        assert_eq!(state.min_raise, 40);
+
+        // A bigger raise sets a bigger minimum
+        let state = raise(&action_config, &state, 250);
+        assert_eq!(state.min_raise, 150);
+        assert!(state.apply_raise(&action_config, 399, ALL_IN).is_err());
+        let state = raise(&action_config, &state, 400);
+        assert_eq!(state.bets[1], 400);
+        assert_eq!(state.min_raise, 150);
+    }
+
+    #[test]
+    fn raises_stop_before_the_max_actions_per_street() {
+        // Each player must still be able to act once within the 4 actions of the street
+        let action_config = ActionConfig::new(3, 1000, 20, 4);
+        let state = start_hand(&action_config, &[1000; 3]);
+        assert!(can_raise(&action_config, &state));
+        let state = raise(&action_config, &state, 60);
+        assert!(can_raise(&action_config, &state));
+        let state = raise(&action_config, &state, 100);
+        assert!(!can_raise(&action_config, &state));
+        assert!(state.get_valid_actions_mask(&action_config).unwrap()[1]);
+
+        let action_config = ActionConfig::new(3, 1000, 20, 9);
+        let state = start_hand(&action_config, &[1000; 3]);
+        let state = raise(&action_config, &state, 60);
+        let state = raise(&action_config, &state, 100);
+        assert!(can_raise(&action_config, &state));
+    }
+
+    #[test]
+    fn seats_sitting_out_are_skipped() {
+        let mut action_config = ActionConfig::new(4, 1000, 20, 9);
+        action_config.ante = 2;
+
+        // Seat 2 sits out: the blinds are posted by seats 0 and 1, seat 3 is first to act
+        let state = start_hand(&action_config, &[1000, 1000, 0, 1000]);
+        assert_eq!(state.players_in_hand, 3);
+        assert_eq!(state.bets[..4], [10, 20, 0, 0]);
+        assert_eq!(state.antes[..4], [2, 2, 0, 2]);
+        assert!(!state.is_player_in[2] && !state.is_all_in[2]);
+        assert_eq!(state.player_to_move, 3);
+
+        // Two players left play heads-up, the button posts the small blind and acts first
+        let state = start_hand(&action_config, &[1000, 0, 0, 1000]);
+        assert_eq!(state.bets[..4], [20, 0, 0, 10]);
+        assert_eq!(state.player_to_move, 3);
+        let state = call(&action_config, &state);
+        assert_eq!(state.player_to_move, 0);
+
+        // A button on an empty seat moves to the next player
+        let state = GameState::new(3, &[1000, 1000, 0], 2);
+        assert_eq!(state.button, 0);
+    }
+
+    #[test]
+    fn short_all_in_does_not_reopen_the_action() {
+        let action_config = ActionConfig::new(4, 1000, 20, 9);
+        let state = start_hand(&action_config, &[1000, 1000, 1000, 130]);
+        let state = raise(&action_config, &state, 100);
+
+        // All-in for 30 more, less than the raise of 80
+        let state = state.apply_action(&action_config, ALL_IN).unwrap().0;
+        assert_eq!(state.bets[3], 130);
+        assert_eq!(state.min_raise, 80);
+
+        // The blinds did not act yet, they can raise, by at least the last full raise
+        assert_eq!(state.player_to_move, 0);
+        assert!(can_raise(&action_config, &state));
+        assert!(state.apply_raise(&action_config, 209, ALL_IN).is_err());
+        let state = call(&action_config, &state);
+        assert!(can_raise(&action_config, &state));
+        let state = call(&action_config, &state);
+
+        // The first raiser can only call or fold
+        assert_eq!(state.player_to_move, 2);
+        assert!(!can_raise(&action_config, &state));
+        assert!(state.apply_raise(&action_config, 300, ALL_IN).is_err());
+        assert!(state.apply_action(&action_config, 0).is_ok());
+        let state = call(&action_config, &state);
+        assert_eq!(state.state_type, StateType::Chance);
+    }
+
+    #[test]
+    fn incomplete_all_ins_adding_to_a_full_raise_reopen_the_action() {
+        let action_config = ActionConfig::new(5, 1000, 20, 9);
+        let state = start_hand(&action_config, &[1000, 1000, 1000, 130, 190]);
+        let state = raise(&action_config, &state, 100);
+
+        // All-ins for 30 then 60 more, 90 over the raise of the first raiser
+        let state = state.apply_action(&action_config, ALL_IN).unwrap().0;
+        let state = state.apply_action(&action_config, ALL_IN).unwrap().0;
+        assert_eq!(state.get_biggest_bet(), 190);
+        assert_eq!(state.min_raise, 80);
+        let state = call(&action_config, &state);
+        let state = state.apply_action(&action_config, 0).unwrap().0;
+
+        assert_eq!(state.player_to_move, 2);
+        assert!(can_raise(&action_config, &state));
+        assert!(state.apply_raise(&action_config, 269, ALL_IN).is_err());
+        let state = raise(&action_config, &state, 270);
+        assert_eq!(state.bets[2], 270);
+    }
+
+    #[test]
+    fn big_blind_keeps_its_option_after_limps() {
+        let action_config = ActionConfig::new(3, 1000, 20, 9);
+        let state = start_hand(&action_config, &[1000; 3]);
+        let state = call(&action_config, &state);
+        let state = call(&action_config, &state);
+
+        assert_eq!(state.state_type, StateType::Play);
+        assert_eq!(state.player_to_move, 1);
+        assert!(can_raise(&action_config, &state));
+        assert!(state.apply_raise(&action_config, 39, ALL_IN).is_err());
+        assert_eq!(raise(&action_config, &state, 40).get_biggest_bet(), 40);
+
+        // The big blind checks and the flop is dealt
+        let state = call(&action_config, &state);
+        assert_eq!(state.state_type, StateType::Chance);
+        assert_eq!(state.get_pot(), 60);
+    }
+}
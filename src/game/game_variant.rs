@@ -0,0 +1,324 @@
+use itertools::Itertools;
+use poker::{Card, Eval, Evaluator, Rank, Suit};
+use serde::{Deserialize, Serialize};
+
+use super::game_error::GameError;
+use super::serialization;
+
+// Rules that depend on the game played: cards dealt and hand evaluation
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum GameVariant {
+    // 2 hole cards, best 5 cards out of the hole cards and the board
+    Holdem,
+    // 4 hole cards, exactly 2 hole cards and 3 board cards are used
+    Omaha,
+    // 2 hole cards from a 36 card deck (2 to 5 removed). A-6-7-8-9 is a straight and a flush
+    // beats a full house. No blinds are posted, only antes, big_blind is the minimum bet.
+    ShortDeck,
+    // Toy game: J, Q and K of one suit, 1 hole card, no board and a single betting round.
+    // The highest card wins.
+    Kuhn,
+    // Toy game: J, Q and K of two suits, 1 hole card, 1 board card dealt for the second betting
+    // round. Pairing the board wins, otherwise the highest card wins.
+    Leduc,
+}
+
+// Strength of a hand at showdown, the better hand is the bigger one. A variant only uses one
+// kind of rank, the order between the two kinds just makes every rank comparable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HandRank {
+    // Computed here for the variants the evaluator does not know, bigger is better
+    Custom(u32),
+    // From poker::Evaluator, a better Eval is bigger
+    Standard(Eval),
+}
+
+// Short-deck hand categories, from the worst to the best
+const SHORT_DECK_HIGH_CARD: u32 = 0;
+const SHORT_DECK_PAIR: u32 = 1;
+const SHORT_DECK_TWO_PAIR: u32 = 2;
+const SHORT_DECK_TRIPS: u32 = 3;
+const SHORT_DECK_STRAIGHT: u32 = 4;
+const SHORT_DECK_FULL_HOUSE: u32 = 5;
+const SHORT_DECK_FLUSH: u32 = 6;
+const SHORT_DECK_QUADS: u32 = 7;
+const SHORT_DECK_STRAIGHT_FLUSH: u32 = 8;
+
+impl GameVariant {
+    pub fn get_hole_card_count(&self) -> usize {
+        match self {
+            GameVariant::Holdem | GameVariant::ShortDeck => 2,
+            GameVariant::Omaha => 4,
+            GameVariant::Kuhn | GameVariant::Leduc => 1,
+        }
+    }
+
+    // Number of betting rounds, the hand goes to showdown after the last one
+    pub fn get_street_count(&self) -> u8 {
+        match self {
+            GameVariant::Holdem | GameVariant::Omaha | GameVariant::ShortDeck => 4,
+            GameVariant::Kuhn => 1,
+            GameVariant::Leduc => 2,
+        }
+    }
+
+    // Board cards visible during a street (1 is preflop)
+    pub fn get_board_card_count(&self, street: u8) -> usize {
+        match self {
+            GameVariant::Holdem | GameVariant::Omaha | GameVariant::ShortDeck => match street {
+                0 | 1 => 0,
+                2 => 3,
+                3 => 4,
+                _ => 5,
+            },
+            GameVariant::Kuhn => 0,
+            GameVariant::Leduc => {
+                if street <= 1 {
+                    0
+                } else {
+                    1
+                }
+            }
+        }
+    }
+
+    // Fixed-limit bets are doubled from this street
+    pub fn get_big_bet_street(&self) -> u8 {
+        match self {
+            GameVariant::Holdem | GameVariant::Omaha | GameVariant::ShortDeck => 3,
+            GameVariant::Kuhn | GameVariant::Leduc => 2,
+        }
+    }
+
+    // Variants played with antes only
+    pub fn has_blinds(&self) -> bool {
+        matches!(self, GameVariant::Holdem | GameVariant::Omaha)
+    }
+
+    // Toy games small enough to compute a best response against any agent
+    pub fn is_toy_game(&self) -> bool {
+        matches!(self, GameVariant::Kuhn | GameVariant::Leduc)
+    }
+
+    pub fn is_card_used(&self, card: &Card) -> bool {
+        let rank = card.rank() as usize;
+        match self {
+            GameVariant::Holdem | GameVariant::Omaha => true,
+            GameVariant::ShortDeck => rank >= self.get_lowest_rank(),
+            GameVariant::Kuhn => {
+                rank >= self.get_lowest_rank()
+                    && rank <= Rank::King as usize
+                    && card.suit() == Suit::Spades
+            }
+            GameVariant::Leduc => {
+                rank >= self.get_lowest_rank()
+                    && rank <= Rank::King as usize
+                    && (card.suit() == Suit::Spades || card.suit() == Suit::Hearts)
+            }
+        }
+    }
+
+    // Index of the lowest rank in the deck, 0 is a two
+    pub fn get_lowest_rank(&self) -> usize {
+        match self {
+            GameVariant::Holdem | GameVariant::Omaha => Rank::Two as usize,
+            GameVariant::ShortDeck => Rank::Six as usize,
+            GameVariant::Kuhn | GameVariant::Leduc => Rank::Jack as usize,
+        }
+    }
+
+    // Number of ranks in the deck, also the width of the card tensors
+    pub fn get_rank_count(&self) -> usize {
+        match self {
+            GameVariant::Kuhn | GameVariant::Leduc => 3,
+            _ => 13 - self.get_lowest_rank(),
+        }
+    }
+
+    // Position of the card rank in the card tensors
+    pub fn get_rank_index(&self, card: &Card) -> usize {
+        card.rank() as usize - self.get_lowest_rank()
+    }
+
+    // Hand rank of folded players, never compared to anything
+    pub fn get_worst_hand_rank(&self) -> HandRank {
+        match self {
+            GameVariant::Holdem | GameVariant::Omaha => HandRank::Standard(Eval::WORST),
+            GameVariant::ShortDeck | GameVariant::Kuhn | GameVariant::Leduc => HandRank::Custom(0),
+        }
+    }
+
+    // Error if the evaluator can not rank the cards, like a duplicated card
+    pub fn evaluate(
+        &self,
+        evaluator: &Evaluator,
+        hand: &[Card],
+        board: &[Card],
+    ) -> Result<HandRank, GameError> {
+        let invalid_hand = |error: String| GameError::InvalidHand {
+            cards: serialization::cards_to_string(&[hand, board].concat()),
+            error,
+        };
+
+        Ok(match self {
+            GameVariant::Holdem => {
+                let mut cards = hand.to_vec();
+                cards.extend_from_slice(board);
+                HandRank::Standard(
+                    evaluator
+                        .evaluate(cards)
+                        .map_err(|error| invalid_hand(error.to_string()))?,
+                )
+            }
+            GameVariant::Omaha => {
+                let mut best = Eval::WORST;
+                for hole_cards in hand.iter().combinations(2) {
+                    for board_cards in board.iter().combinations(3) {
+                        let cards: Vec<Card> = hole_cards
+                            .iter()
+                            .chain(board_cards.iter())
+                            .map(|card| **card)
+                            .collect();
+                        let eval = evaluator
+                            .evaluate(cards)
+                            .map_err(|error| invalid_hand(error.to_string()))?;
+                        if eval.is_better_than(best) {
+                            best = eval;
+                        }
+                    }
+                }
+                HandRank::Standard(best)
+            }
+            GameVariant::ShortDeck => {
+                // The standard evaluator does not know the short-deck rankings
+                let best = hand
+                    .iter()
+                    .chain(board.iter())
+                    .combinations(5)
+                    .map(|cards| Self::evaluate_short_deck_five(&cards))
+                    .max()
+                    .unwrap_or(0);
+                HandRank::Custom(best)
+            }
+            GameVariant::Kuhn => HandRank::Custom(hand[0].rank() as u32),
+            GameVariant::Leduc => {
+                let rank = hand[0].rank() as u32;
+                if rank == board[0].rank() as u32 {
+                    HandRank::Custom(16 + rank)
+                } else {
+                    HandRank::Custom(rank)
+                }
+            }
+        })
+    }
+
+    // Category in the high bits, then the ranks breaking ties from the most to the least important
+    fn evaluate_short_deck_five(cards: &[&Card]) -> u32 {
+        // Ranks sorted by count then by rank, so pairs come before kickers
+        let mut rank_counts: Vec<(u32, u32)> = Vec::new();
+        for card in cards.iter() {
+            let rank = card.rank() as u32;
+            match rank_counts.iter_mut().find(|(r, _)| *r == rank) {
+                Some((_, count)) => *count += 1,
+                None => rank_counts.push((rank, 1)),
+            }
+        }
+        rank_counts.sort_by(|a, b| b.1.cmp(&a.1).then(b.0.cmp(&a.0)));
+
+        let is_flush = cards.iter().all(|card| card.suit() == cards[0].suit());
+
+        // With 5 different ranks, the hand is a straight if they follow each other. The ace
+        // also plays low under the six (A-6-7-8-9), the nine is then the high card
+        let mut straight_high: Option<u32> = None;
+        if rank_counts.len() == 5 {
+            let high = rank_counts[0].0;
+            let low = rank_counts[4].0;
+            if high - low == 4 {
+                straight_high = Some(high);
+            } else if high == Rank::Ace as u32
+                && rank_counts[1].0 == Rank::Nine as u32
+                && low == Rank::Six as u32
+            {
+                straight_high = Some(Rank::Nine as u32);
+            }
+        }
+
+        let category = match (straight_high, is_flush, rank_counts[0].1, rank_counts[1].1) {
+            (Some(_), true, _, _) => SHORT_DECK_STRAIGHT_FLUSH,
+            (_, _, 4, _) => SHORT_DECK_QUADS,
+            (_, true, _, _) => SHORT_DECK_FLUSH,
+            (_, _, 3, 2) => SHORT_DECK_FULL_HOUSE,
+            (Some(_), false, _, _) => SHORT_DECK_STRAIGHT,
+            (_, _, 3, _) => SHORT_DECK_TRIPS,
+            (_, _, 2, 2) => SHORT_DECK_TWO_PAIR,
+            (_, _, 2, _) => SHORT_DECK_PAIR,
+            _ => SHORT_DECK_HIGH_CARD,
+        };
+
+        // 4 bits per rank, a straight only needs its high card
+        let tie_breakers: Vec<u32> = match straight_high {
+            Some(high) => vec![high],
+            None => rank_counts.iter().map(|(rank, _)| *rank).collect(),
+        };
+        let mut value = category;
+        for i in 0..5 {
+            value = (value << 4) | tie_breakers.get(i).map_or(0, |rank| rank + 1);
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::serialization::cards_from_string;
+
+    fn evaluate(game_variant: GameVariant, hand: &str, board: &str) -> HandRank {
+        game_variant
+            .evaluate(
+                &Evaluator::new(),
+                &cards_from_string(hand).unwrap(),
+                &cards_from_string(board).unwrap(),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn short_deck_ranks() {
+        let short_deck = |hand, board| evaluate(GameVariant::ShortDeck, hand, board);
+        let flush = short_deck("AsKs", "6s9sJs6h6d");
+        let full_house = short_deck("AhAd", "6s6c6h7d8c");
+        let trips = short_deck("KhKd", "Ks8c9h6dQc");
+        let wheel = short_deck("Ah6d", "7s8c9hKdKc");
+        assert!(flush > full_house);
+        assert!(wheel > trips);
+        assert!(short_deck("Th6d", "7s8c9hKdKc") > wheel);
+        assert!(short_deck("Ah6h", "7h8h9hKdKc") > short_deck("KhKd", "KsKc9h6dQc"));
+        assert!(short_deck("AhAd", "7s8cThJdQc") > short_deck("KhKd", "7s8cThJdAc"));
+        assert!(short_deck("AhKd", "7s8cThJd6c") == short_deck("AcKh", "7d8sTcJh6d"));
+        assert!(full_house > GameVariant::ShortDeck.get_worst_hand_rank());
+    }
+
+    #[test]
+    fn duplicated_cards_are_an_error() {
+        let hand = cards_from_string("AsAs").unwrap();
+        let board = cards_from_string("Kd7c2h").unwrap();
+        assert!(matches!(
+            GameVariant::Holdem.evaluate(&Evaluator::new(), &hand, &board),
+            Err(GameError::InvalidHand { .. })
+        ));
+    }
+
+    #[test]
+    fn toy_game_ranks() {
+        let kuhn = |hand| evaluate(GameVariant::Kuhn, hand, "");
+        assert!(kuhn("Ks") > kuhn("Qs"));
+        assert!(kuhn("Qs") > kuhn("Js"));
+
+        // Pairing the board beats any high card
+        let leduc = |hand| evaluate(GameVariant::Leduc, hand, "Jh");
+        assert!(leduc("Js") > leduc("Ks"));
+        assert!(leduc("Ks") > leduc("Qs"));
+        assert!(leduc("Qs") == leduc("Qh"));
+    }
+}
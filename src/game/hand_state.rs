@@ -1,17 +1,44 @@
 use super::action::ActionConfig;
 use super::action_state::ActionState;
+use super::deal::Deal;
+use super::game_state::GameState;
+use super::position::Position;
 use candle_core::Tensor;
 use poker::Card;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct HandState {
     pub traverser: u32,
+    #[serde(with = "super::serialization::cards")]
     pub hand: Vec<Card>,
+    #[serde(with = "super::serialization::cards")]
     pub board: Vec<Card>,
+    pub button: u32,
+    // Position of each seat for this hand, None for the seats sitting out
+    pub positions: Vec<Option<Position>>,
+    // Stack of each seat at the start of the hand
+    pub stacks: Vec<u32>,
     pub action_states: Vec<ActionState>,
 }
 
 impl HandState {
+    // State of the hand seen by the traverser before any action
+    pub fn new(traverser: u32, deal: &Deal, game_state: &GameState) -> HandState {
+        HandState {
+            traverser,
+            hand: deal.hands[traverser as usize].clone(),
+            board: deal.board.clone(),
+            button: game_state.button,
+            positions: Position::from_stacks(
+                game_state.button,
+                &game_state.stacks[..game_state.player_count as usize],
+            ),
+            stacks: game_state.stacks[..game_state.player_count as usize].to_vec(),
+            action_states: Vec::new(),
+        }
+    }
+
     pub fn get_all_tensors(
         &self,
         action_config: &ActionConfig,
@@ -54,41 +81,31 @@ impl HandState {
         current_state_index: usize,
     ) -> Result<(Tensor, Tensor), candle_core::Error> {
         // Create card tensor
-        // Shape is (street_cnt + 1 for all cards) x number_of_suits x number_of_ranks
-        let mut card_vecs: Vec<Vec<Vec<f32>>> = vec![vec![vec![0.0; 13]; 4]; 6];
+        // Shape is (street_cnt + 1 for all cards) x number_of_suits x number_of_ranks, the ranks
+        // are the ones in the deck of the variant
+        let game_variant = action_config.game_variant;
+        let mut card_vecs: Vec<Vec<Vec<f32>>> =
+            vec![vec![vec![0.0; game_variant.get_rank_count()]; 4]; 6];
 
-        // Set hand cards
+        // Set hole cards, the shape is the same whatever the number of cards the variant deals
         for card in self.hand.iter() {
-            card_vecs[0][card.suit() as usize][card.rank() as usize] = 1.0;
-            card_vecs[5][card.suit() as usize][card.rank() as usize] = 1.0;
+            card_vecs[0][card.suit() as usize][game_variant.get_rank_index(card)] = 1.0;
+            card_vecs[5][card.suit() as usize][game_variant.get_rank_index(card)] = 1.0;
         }
 
-        // Set flop cards
-        if street > 1 {
-            for i in 0..3 {
-                let card = self.board[i];
-                card_vecs[1][card.suit() as usize][card.rank() as usize] = 1.0;
-                card_vecs[4][card.suit() as usize][card.rank() as usize] = 1.0;
-                card_vecs[5][card.suit() as usize][card.rank() as usize] = 1.0;
+        // Set the board cards dealt for each street (flop, turn and river in hold'em), each
+        // street has its own channel
+        for board_street in 2..=street {
+            let first_card = game_variant.get_board_card_count(board_street - 1);
+            let last_card = game_variant.get_board_card_count(board_street);
+            for card in self.board[first_card..last_card].iter() {
+                let channel = board_street as usize - 1;
+                card_vecs[channel][card.suit() as usize][game_variant.get_rank_index(card)] = 1.0;
+                card_vecs[4][card.suit() as usize][game_variant.get_rank_index(card)] = 1.0;
+                card_vecs[5][card.suit() as usize][game_variant.get_rank_index(card)] = 1.0;
             }
         }
 
-        // Set turn cards
-        if street > 2 {
-            let card = self.board[3];
-            card_vecs[2][card.suit() as usize][card.rank() as usize] = 1.0;
-            card_vecs[4][card.suit() as usize][card.rank() as usize] = 1.0;
-            card_vecs[5][card.suit() as usize][card.rank() as usize] = 1.0;
-        }
-
-        // Set river cards
-        if street > 3 {
-            let card = self.board[4];
-            card_vecs[3][card.suit() as usize][card.rank() as usize] = 1.0;
-            card_vecs[4][card.suit() as usize][card.rank() as usize] = 1.0;
-            card_vecs[5][card.suit() as usize][card.rank() as usize] = 1.0;
-        }
-
         // Print card_vecs as matrix
         // println!("-------------------");
 
@@ -107,12 +124,17 @@ impl HandState {
         let mut action_vecs: Vec<Vec<Vec<f32>>> =
             vec![
                 vec![
-                    vec![0.0; 3 + action_config.postflop_raise_sizes.len()];
+                    vec![0.0; action_config.get_action_count()];
                     action_config.player_count as usize + 2
                 ];
                 4 * action_config.max_actions_per_street as usize
             ];
 
+        // Players are ordered from the left of the button so the input does not depend on the seat
+        let player_count = action_config.player_count;
+        let player_row =
+            |player: u32| ((player + 2 * player_count - self.button - 1) % player_count) as usize;
+
         let mut action_cnt: usize = 0;
         let mut current_street: u8 = 0;
         for action_state_it in self.action_states.iter().take(current_state_index + 1) {
@@ -134,7 +156,7 @@ impl HandState {
 
             // Set player action in tensor
             action_vecs[current_street as usize * action_config.max_actions_per_street as usize
-                + action_cnt][action_state_it.player_to_move as usize]
+                + action_cnt][player_row(action_state_it.player_to_move)]
                 [action_state_it.action_taken_index] = 1.0;
 
             // Increment sum of actions
@@ -159,7 +181,7 @@ impl HandState {
         // if self.action_states.len() > 2 {
         //     for i in 0..4 * action_config.max_actions_per_street as usize {
         //         for j in 0..action_config.player_count as usize + 2 {
-        //             for k in 0..3 + action_config.postflop_raise_sizes.len() {
+        //             for k in 0..action_config.get_action_count() {
         //                 print!("{}", action_vecs[i][j][k]);
         //             }
         //             println!();
@@ -169,7 +191,7 @@ impl HandState {
         // }
         // for i in 0..4 * action_config.max_actions_per_street as usize {
         //     for j in 0..action_config.player_count as usize + 2 {
-        //         for k in 0..3 + action_config.postflop_raise_sizes.len() {
+        //         for k in 0..action_config.get_action_count() {
         //             print!("{}", action_vecs[i][j][k]);
         //         }
         //         println!();
@@ -199,6 +221,17 @@ impl HandState {
         )
     }
 
+    // Most the traverser can win in this hand, used to normalize rewards
+    pub fn get_reward_ratio(&self) -> f32 {
+        let traverser_stack = self.stacks[self.traverser as usize];
+        self.stacks
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != self.traverser as usize)
+            .map(|(_, stack)| (*stack).min(traverser_stack) as f32)
+            .sum()
+    }
+
     pub fn get_traverser_action_states(&self) -> Vec<&ActionState> {
         let result: Vec<&ActionState> = self
             .action_states
@@ -0,0 +1,130 @@
+// Independent Chip Model (Malmuth-Harville): a player finishes first with probability stack / total
+// chips, then the next places are given the same way among the players left.
+
+// Prize equity of each player, payouts[i] is the prize of place i + 1. Players with no chips
+// share the places left.
+pub fn get_icm_equities(stacks: &[f32], payouts: &[f32]) -> Vec<f32> {
+    get_ranked_icm_equities(stacks, stacks, payouts)
+}
+
+// Same as get_icm_equities, but the players with no chips are ranked by ranking_stacks, their
+// stacks before they busted: the larger stack finishes higher, equal stacks share their places
+fn get_ranked_icm_equities(stacks: &[f32], ranking_stacks: &[f32], payouts: &[f32]) -> Vec<f32> {
+    let mut equities = vec![0.0; stacks.len()];
+    let mut placed = vec![false; stacks.len()];
+    add_place_equities(
+        stacks,
+        ranking_stacks,
+        payouts,
+        0,
+        1.0,
+        &mut placed,
+        &mut equities,
+    );
+    equities
+}
+
+// Change of prize equity of each player over a hand, counted in chips: the prize pool is worth
+// all the chips at the table, so the rewards keep the scale of chip rewards. Players busted in
+// the hand finish in the order of their starting stacks.
+pub fn get_icm_rewards(starting_stacks: &[u32], chip_rewards: &[f32], payouts: &[f32]) -> Vec<f32> {
+    let total_chips: f32 = starting_stacks.iter().map(|stack| *stack as f32).sum();
+    let prize_pool: f32 = payouts.iter().sum();
+
+    let stacks_before: Vec<f32> = starting_stacks.iter().map(|stack| *stack as f32).collect();
+    let stacks_after: Vec<f32> = stacks_before
+        .iter()
+        .zip(chip_rewards.iter())
+        .map(|(stack, reward)| (stack + reward).max(0.0))
+        .collect();
+
+    let equities_before = get_icm_equities(&stacks_before, payouts);
+    let equities_after = get_ranked_icm_equities(&stacks_after, &stacks_before, payouts);
+    equities_after
+        .iter()
+        .zip(equities_before.iter())
+        .map(|(after, before)| (after - before) * total_chips / prize_pool)
+        .collect()
+}
+
+// Gives the prize of place to each player left, weighted by the probability of the places above.
+// Paid places are usually few, so the recursion stays small.
+fn add_place_equities(
+    stacks: &[f32],
+    ranking_stacks: &[f32],
+    payouts: &[f32],
+    place: usize,
+    probability: f32,
+    placed: &mut Vec<bool>,
+    equities: &mut Vec<f32>,
+) {
+    if place >= payouts.len() {
+        return;
+    }
+
+    let players_left: Vec<usize> = (0..stacks.len()).filter(|p| !placed[*p]).collect();
+    if players_left.is_empty() {
+        return;
+    }
+
+    let total: f32 = players_left.iter().map(|p| stacks[*p]).sum();
+    if total <= 0.0 {
+        // Only busted players are left, the ones with the largest ranking stack take the next
+        // places and share their prizes
+        let mut players_left = players_left;
+        players_left.sort_by(|a, b| ranking_stacks[*b].total_cmp(&ranking_stacks[*a]));
+        let mut group_place = place;
+        for group in players_left.chunk_by(|a, b| ranking_stacks[*a] == ranking_stacks[*b]) {
+            let first_place = group_place.min(payouts.len());
+            let last_place = (group_place + group.len()).min(payouts.len());
+            let share = payouts[first_place..last_place].iter().sum::<f32>() / group.len() as f32;
+            for player in group {
+                equities[*player] += probability * share;
+            }
+            group_place += group.len();
+        }
+        return;
+    }
+
+    for player in players_left {
+        if stacks[player] <= 0.0 {
+            continue;
+        }
+        let player_probability = probability * stacks[player] / total;
+        equities[player] += player_probability * payouts[place];
+
+        placed[player] = true;
+        add_place_equities(
+            stacks,
+            ranking_stacks,
+            payouts,
+            place + 1,
+            player_probability,
+            placed,
+            equities,
+        );
+        placed[player] = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn busted_players_finish_in_the_order_of_their_starting_stacks() {
+        let payouts = [0.5, 0.3, 0.2];
+        let equities = get_ranked_icm_equities(&[0.0, 0.0, 300.0], &[100.0, 50.0, 150.0], &payouts);
+        assert_eq!(equities, vec![0.3, 0.2, 0.5]);
+
+        let rewards = get_icm_rewards(&[100, 50, 150], &[-100.0, -50.0, 150.0], &payouts);
+        assert!(rewards[0] > rewards[1]);
+    }
+
+    #[test]
+    fn busted_players_with_equal_starting_stacks_share_their_places() {
+        let payouts = [0.5, 0.3, 0.2];
+        let equities = get_ranked_icm_equities(&[0.0, 0.0, 300.0], &[100.0; 3], &payouts);
+        assert_eq!(equities, vec![0.25, 0.25, 0.5]);
+    }
+}
@@ -0,0 +1,399 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use super::action::{
+    Action, ActionConfig, ActionType, AllInReward, BettingStructure, RaiseSize, Rake,
+    StartingStacks,
+};
+use super::deal::Deal;
+use super::game_state::{GameState, StateType};
+use super::game_variant::GameVariant;
+use super::tree::Tree;
+use crate::agent::agent_random::AgentRandom;
+use crate::agent::Agent;
+
+// Rules of the game tree checked by a Tree in validation mode. A broken rule is returned as an
+// error describing the state instead of a panic later in the hand.
+
+// Checks the move from parent to child with action_index (0 for a chance state), then the child
+// itself. History is the actions taken to reach the child.
+pub fn check_transition(
+    action_config: &ActionConfig,
+    parent: &GameState,
+    action_index: usize,
+    child: &GameState,
+    history: &[Action],
+    deal: &Deal,
+    starting_stacks: &[u32],
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Folded players never come back
+    for player in 0..parent.player_count as usize {
+        if child.is_player_in[player] && !parent.is_player_in[player] {
+            return Err(format!("Player {} is back in the hand after folding", player).into());
+        }
+    }
+
+    if parent.state_type == StateType::Play {
+        let valid_actions_mask = parent.get_valid_actions_mask(action_config)?;
+        let action_count = valid_actions_mask.len();
+        if action_index >= action_count || !valid_actions_mask[action_index] {
+            return Err(format!("Action {} taken but not valid", action_index).into());
+        }
+
+        // One action by the player to move, the other players do not move chips
+        let action = history
+            .last()
+            .ok_or("An action must be added to the history")?;
+        if action.player_index != parent.player_to_move {
+            return Err(format!(
+                "Action made by player {} but player {} was to move",
+                action.player_index, parent.player_to_move
+            )
+            .into());
+        }
+        if child.street != parent.street || action.street != parent.street {
+            return Err("An action must not change the street".into());
+        }
+        let expected_type = match action_index {
+            0 => matches!(action.action_type, ActionType::Fold),
+            1 => matches!(action.action_type, ActionType::Call),
+            index if index == action_count - 1 => {
+                matches!(action.action_type, ActionType::AllIn)
+            }
+            _ => matches!(action.action_type, ActionType::Raise),
+        };
+        if !expected_type {
+            return Err(
+                format!("Action {} led to a {:?}", action_index, action.action_type).into(),
+            );
+        }
+        for player in 0..parent.player_count as usize {
+            if player as i32 != parent.player_to_move
+                && child.get_contribution(player as u32) != parent.get_contribution(player as u32)
+            {
+                return Err(format!("Player {} put chips in out of turn", player).into());
+            }
+        }
+        if child.bets[parent.player_to_move as usize] < parent.bets[parent.player_to_move as usize]
+        {
+            return Err("A bet can not go down during a street".into());
+        }
+    } else if child.street != parent.street + 1 {
+        return Err("Dealing cards must only move to the next street".into());
+    }
+
+    check_state(action_config, child, history, deal, starting_stacks)
+}
+
+// Checks a single state against the stacks the players had at the start of the hand
+pub fn check_state(
+    action_config: &ActionConfig,
+    state: &GameState,
+    history: &[Action],
+    deal: &Deal,
+    starting_stacks: &[u32],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let player_count = state.player_count as usize;
+
+    // Chips only move between the stacks and the pot
+    for (player, starting_stack) in starting_stacks.iter().enumerate().take(player_count) {
+        let total = state.stacks[player] + state.get_contribution(player as u32);
+        if total != *starting_stack {
+            return Err(format!(
+                "Player {} has {} chips in stack and pot but started with {}",
+                player, total, starting_stack
+            )
+            .into());
+        }
+        if state.is_all_in[player] && state.stacks[player] != 0 {
+            return Err(format!("Player {} is all-in with chips left", player).into());
+        }
+        if state.is_sitting_out(player as u32) && state.is_player_in[player] {
+            return Err(format!("Player {} sits out but is in the hand", player).into());
+        }
+    }
+
+    let players_in = state.is_player_in[..player_count]
+        .iter()
+        .filter(|in_hand| **in_hand)
+        .count();
+    if players_in == 0 || players_in as u32 != state.players_in_hand {
+        return Err(format!(
+            "{} players in hand but {} counted",
+            players_in, state.players_in_hand
+        )
+        .into());
+    }
+
+    check_history(state, history)?;
+
+    match state.state_type {
+        StateType::Play => {
+            let player = state.player_to_move;
+            if player < 0 || player as usize >= player_count {
+                return Err(format!("Player to move {} is not a seat", player).into());
+            }
+            if !state.is_player_in[player as usize] {
+                return Err(format!("Player {} is to move but folded", player).into());
+            }
+            if state.stacks[player as usize] == 0 || state.is_all_in[player as usize] {
+                return Err(format!("Player {} is to move but all-in", player).into());
+            }
+        }
+        StateType::Terminal => {
+            let hand_result = state.get_hand_result(action_config, deal)?;
+            let rewards = hand_result.rewards;
+            let chip_results = hand_result.chip_results;
+
+            // Chips are exact: the pot is paid out to the last chip, minus the rake
+            let rake = action_config.rake.get_rake(state) as i32;
+            if chip_results.iter().sum::<i32>() != -rake {
+                return Err(format!(
+                    "Chip results {:?} do not sum to minus the rake {}",
+                    chip_results, rake
+                )
+                .into());
+            }
+            for (player, chip_result) in chip_results.iter().enumerate() {
+                if *chip_result < -(state.get_contribution(player as u32) as i32) {
+                    return Err(format!(
+                        "Player {} loses {} chips but only put {} in",
+                        player,
+                        -chip_result,
+                        state.get_contribution(player as u32)
+                    )
+                    .into());
+                }
+            }
+
+            // Rewards are averaged over runouts or converted to ICM, the tolerance grows with the pot
+            let tolerance = 1e-3 + state.get_pot() as f32 * 1e-5;
+            // The rake leaves the table, ICM equity does not change with the chips in play
+            let reward_rake = if action_config.payouts.is_empty() {
+                rake as f32
+            } else {
+                0.0
+            };
+            if (rewards.iter().sum::<f32>() + reward_rake).abs() > tolerance {
+                return Err(format!(
+                    "Rewards {:?} do not sum to minus the rake {}",
+                    rewards, reward_rake
+                )
+                .into());
+            }
+            // With ICM, a player also wins or loses equity when the others bust
+            if action_config.payouts.is_empty() {
+                for (player, reward) in rewards.iter().enumerate() {
+                    if *reward < -(state.get_contribution(player as u32) as f32) - tolerance {
+                        return Err(format!(
+                            "Player {} loses {} but only put {} in",
+                            player,
+                            -reward,
+                            state.get_contribution(player as u32)
+                        )
+                        .into());
+                    }
+                }
+            }
+        }
+        StateType::Chance => {}
+    }
+
+    Ok(())
+}
+
+// Actions are made by seats of the hand, in street order, and nobody acts after folding
+fn check_history(state: &GameState, history: &[Action]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut street = 0;
+    let mut folded = vec![false; state.player_count as usize];
+    for action in history.iter() {
+        if action.player_index < 0 || action.player_index as u32 >= state.player_count {
+            return Err(format!("Action by unknown player {}", action.player_index).into());
+        }
+        if action.street < street || action.street > state.street {
+            return Err(format!("Action of street {} out of order", action.street).into());
+        }
+        if folded[action.player_index as usize] {
+            return Err(format!("Player {} acts after folding", action.player_index).into());
+        }
+        if matches!(action.action_type, ActionType::Fold) {
+            folded[action.player_index as usize] = true;
+        }
+        street = action.street;
+    }
+    Ok(())
+}
+
+// Plays hand_count hands between random agents with every rule checked, on random configs of
+// every variant and betting structure. Returns the first broken rule. progress is called with
+// the number of hands checked so far after each config.
+pub fn check_random_hands(
+    hand_count: u64,
+    seed: u64,
+    progress: &mut dyn FnMut(u64),
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let device = candle_core::Device::Cpu;
+    let hands_per_config = 1000;
+
+    let mut hands_played = 0;
+    while hands_played < hand_count {
+        let action_config = create_random_config(&mut rng);
+        let player_count = action_config.player_count;
+        let agents: Vec<Arc<Box<dyn Agent>>> = (0..player_count)
+            .map(|_| Arc::new(Box::new(AgentRandom {}) as Box<dyn Agent>))
+            .collect();
+
+        let mut tree = Tree::new(player_count, &action_config, rng.gen());
+        tree.set_check_invariants(true);
+
+        // Some tables have seats sitting out, at least two players are left
+        if player_count > 2 && rng.gen_bool(0.2) {
+            let mut stacks = action_config.create_starting_stacks(&mut rng)?;
+            let out_count = rng.gen_range(1..=player_count as usize - 2);
+            for seat in rand::seq::index::sample(&mut rng, player_count as usize, out_count) {
+                stacks[seat] = 0;
+            }
+            tree.set_stacks(Some(stacks))?;
+        }
+        for hand in 0..hands_per_config.min(hand_count - hands_played) {
+            tree.move_button();
+            let result = if hand % 2 == 0 {
+                tree.play_one_hand(&agents, &device, true).map(|_| ())
+            } else {
+                tree.traverse(hand as u32 % player_count, &agents, &device, true, 0.1)
+            };
+            if let Err(err) = result {
+                return Err(format!(
+                    "Hand {} of {:?} with {} players ({:?}): {}",
+                    hands_played + hand,
+                    action_config.game_variant,
+                    player_count,
+                    action_config.betting_structure,
+                    err
+                )
+                .into());
+            }
+        }
+        hands_played += hands_per_config.min(hand_count - hands_played);
+        progress(hands_played);
+    }
+
+    Ok(())
+}
+
+// Random valid config, invalid draws are drawn again
+fn create_random_config(rng: &mut StdRng) -> ActionConfig {
+    let raise_sizes = [
+        RaiseSize::Pot(0.33),
+        RaiseSize::Pot(0.5),
+        RaiseSize::Pot(1.0),
+        RaiseSize::Pot(2.0),
+        RaiseSize::BigBlind(2.5),
+        RaiseSize::BigBlind(10.0),
+        RaiseSize::LastBet(2.0),
+        RaiseSize::LastBet(3.0),
+    ];
+
+    loop {
+        let game_variant = *[
+            GameVariant::Holdem,
+            GameVariant::Omaha,
+            GameVariant::ShortDeck,
+            GameVariant::Kuhn,
+            GameVariant::Leduc,
+        ]
+        .choose(rng)
+        .unwrap();
+
+        let mut action_config = match game_variant {
+            GameVariant::Kuhn => ActionConfig::new_kuhn(),
+            GameVariant::Leduc => ActionConfig::new_leduc(),
+            _ => {
+                let big_blind = rng.gen_range(2..=20);
+                let mut action_config = ActionConfig::new(
+                    rng.gen_range(2..=9),
+                    big_blind * rng.gen_range(5..=150),
+                    big_blind,
+                    rng.gen_range(4..=12),
+                );
+                action_config.game_variant = game_variant;
+                action_config.ante = rng.gen_range(0..=big_blind / 2);
+                if rng.gen_bool(0.5) {
+                    action_config.big_blind_ante = rng.gen_range(0..=big_blind);
+                }
+                action_config.straddle = rng.gen_bool(0.3);
+                action_config.betting_structure = match rng.gen_range(0..3) {
+                    0 => BettingStructure::NoLimit,
+                    1 => BettingStructure::PotLimit,
+                    _ => BettingStructure::FixedLimit {
+                        small_bet: big_blind,
+                        big_bet: 2 * big_blind,
+                        raise_cap: rng.gen_range(1..=5),
+                    },
+                };
+                if action_config.is_no_limit()
+                    || matches!(action_config.betting_structure, BettingStructure::PotLimit)
+                {
+                    for street in 1..=4 {
+                        let size_count = rng.gen_range(0..=4);
+                        let sizes: Vec<RaiseSize> = raise_sizes
+                            .choose_multiple(rng, size_count)
+                            .copied()
+                            .collect();
+                        match street {
+                            1 => action_config.preflop_raise_sizes = sizes,
+                            2 => action_config.flop_raise_sizes = sizes,
+                            3 => action_config.turn_raise_sizes = sizes,
+                            _ => action_config.river_raise_sizes = sizes,
+                        }
+                    }
+                }
+                action_config
+            }
+        };
+
+        if rng.gen_bool(0.5) {
+            let min = action_config.buy_in / 5;
+            action_config.starting_stacks = StartingStacks::Uniform {
+                min,
+                max: action_config.buy_in * 2,
+            };
+        }
+
+        // Some tables are tournament tables, with ICM rewards
+        if rng.gen_bool(0.2) {
+            action_config.payouts = vec![0.5, 0.3, 0.2];
+        }
+
+        // Some cash tables are raked
+        if rng.gen_bool(0.2) {
+            let cap = rng.gen_range(1..=10) * action_config.big_blind;
+            action_config.rake = Rake::new(rng.gen_range(1.0..=10.0), Some(cap), rng.gen());
+        }
+
+        // Some all-in showdowns are paid by their equity
+        if rng.gen_bool(0.2) {
+            action_config.all_in_reward = AllInReward::SampledEquity(rng.gen_range(1..=20));
+        }
+
+        if action_config.validate().is_ok() {
+            return action_config;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_hands_follow_the_rules() {
+        let mut hands_checked = 0;
+        check_random_hands(3000, 0, &mut |hands| hands_checked = hands).unwrap();
+        assert_eq!(hands_checked, 3000);
+    }
+}
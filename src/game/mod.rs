@@ -1,10 +1,18 @@
-mod state;
-mod state_chance;
-mod state_data;
-mod state_play;
-mod state_terminal;
-
 pub mod action;
 pub mod action_state;
+pub mod action_translation;
+pub mod deal;
+pub mod environment;
+pub mod exploitability;
+pub mod game_error;
+pub mod game_state;
+pub mod game_variant;
 pub mod hand_state;
+pub mod icm;
+pub mod invariants;
+pub mod position;
+pub mod serialization;
+pub mod showdown;
+pub mod spot;
+pub mod state_arena;
 pub mod tree;
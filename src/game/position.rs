@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Position {
+    Button,
+    SmallBlind,
+    BigBlind,
+    UnderTheGun,
+    // Seats between UTG and the hijack, 1 is UTG+1
+    Middle(u8),
+    Hijack,
+    Cutoff,
+}
+
+impl Position {
+    pub fn from_seat(seat: u32, button: u32, player_count: u32) -> Position {
+        let offset = (seat + player_count - button % player_count) % player_count;
+
+        // Heads-up, the button is also the small blind
+        if player_count == 2 {
+            return if offset == 0 {
+                Position::Button
+            } else {
+                Position::BigBlind
+            };
+        }
+
+        match offset {
+            0 => Position::Button,
+            1 => Position::SmallBlind,
+            2 => Position::BigBlind,
+            _ if player_count >= 4 && offset == player_count - 1 => Position::Cutoff,
+            _ if player_count >= 5 && offset == player_count - 2 => Position::Hijack,
+            3 => Position::UnderTheGun,
+            _ => Position::Middle((offset - 3) as u8),
+        }
+    }
+
+    pub fn from_button(button: u32, player_count: u32) -> Vec<Position> {
+        (0..player_count)
+            .map(|seat| Position::from_seat(seat, button, player_count))
+            .collect()
+    }
+
+    // Positions of a table where the seats with an empty stack sit out, they have no position
+    pub fn from_stacks(button: u32, stacks: &[u32]) -> Vec<Option<Position>> {
+        let seat_count = stacks.len() as u32;
+        let seated: Vec<u32> = (0..seat_count)
+            .map(|i| (button + i) % seat_count)
+            .filter(|seat| stacks[*seat as usize] > 0)
+            .collect();
+
+        let mut positions = vec![None; stacks.len()];
+        for (offset, seat) in seated.iter().enumerate() {
+            positions[*seat as usize] =
+                Some(Position::from_seat(offset as u32, 0, seated.len() as u32));
+        }
+        positions
+    }
+
+    pub fn to_short_string(self) -> String {
+        match self {
+            Position::Button => String::from("BTN"),
+            Position::SmallBlind => String::from("SB"),
+            Position::BigBlind => String::from("BB"),
+            Position::UnderTheGun => String::from("UTG"),
+            Position::Middle(i) => format!("UTG+{}", i),
+            Position::Hijack => String::from("HJ"),
+            Position::Cutoff => String::from("CO"),
+        }
+    }
+}
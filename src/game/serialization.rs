@@ -0,0 +1,247 @@
+use poker::{Card, Rank, Suit};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+// Encodings of the game types to save rollouts, spots and configs. JSON is readable and diffable,
+// the binary encoding (bincode) is compact. Both are stable: fields are encoded by name in JSON
+// and in declaration order in binary, and cards are written as their rank and suit characters.
+
+pub fn to_json<T: Serialize>(value: &T) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(serde_json::to_string_pretty(value)?)
+}
+
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, Box<dyn std::error::Error>> {
+    Ok(serde_json::from_str(json)?)
+}
+
+pub fn to_binary<T: Serialize>(value: &T) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    Ok(bincode::serialize(value)?)
+}
+
+pub fn from_binary<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Box<dyn std::error::Error>> {
+    Ok(bincode::deserialize(bytes)?)
+}
+
+const RANK_CHARS: [(Rank, char); 13] = [
+    (Rank::Two, '2'),
+    (Rank::Three, '3'),
+    (Rank::Four, '4'),
+    (Rank::Five, '5'),
+    (Rank::Six, '6'),
+    (Rank::Seven, '7'),
+    (Rank::Eight, '8'),
+    (Rank::Nine, '9'),
+    (Rank::Ten, 'T'),
+    (Rank::Jack, 'J'),
+    (Rank::Queen, 'Q'),
+    (Rank::King, 'K'),
+    (Rank::Ace, 'A'),
+];
+
+const SUIT_CHARS: [(Suit, char); 4] = [
+    (Suit::Spades, 's'),
+    (Suit::Hearts, 'h'),
+    (Suit::Diamonds, 'd'),
+    (Suit::Clubs, 'c'),
+];
+
+// Cards written one after the other, like "AsKd"
+pub fn cards_to_string(cards: &[Card]) -> String {
+    let mut string = String::with_capacity(cards.len() * 2);
+    for card in cards.iter() {
+        let rank = RANK_CHARS
+            .iter()
+            .find(|(rank, _)| *rank == card.rank())
+            .unwrap();
+        let suit = SUIT_CHARS
+            .iter()
+            .find(|(suit, _)| *suit == card.suit())
+            .unwrap();
+        string.push(rank.1);
+        string.push(suit.1);
+    }
+    string
+}
+
+pub fn cards_from_string(string: &str) -> Result<Vec<Card>, String> {
+    let chars: Vec<char> = string.chars().collect();
+    if chars.len() % 2 == 1 {
+        return Err(format!("Cards {} are not rank and suit pairs", string));
+    }
+
+    chars
+        .chunks(2)
+        .map(|card| {
+            let rank = RANK_CHARS.iter().find(|(_, c)| *c == card[0]);
+            let suit = SUIT_CHARS.iter().find(|(_, c)| *c == card[1]);
+            match (rank, suit) {
+                (Some((rank, _)), Some((suit, _))) => Ok(Card::new(*rank, *suit)),
+                _ => Err(format!("Invalid card {}{}", card[0], card[1])),
+            }
+        })
+        .collect()
+}
+
+// For #[serde(with = "...")] on the card fields
+pub mod cards {
+    use poker::Card;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(cards: &[Card], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::cards_to_string(cards))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Card>, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        super::cards_from_string(&string).map_err(serde::de::Error::custom)
+    }
+}
+
+// One string of cards per player
+pub mod hands {
+    use poker::Card;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(hands: &[Vec<Card>], serializer: S) -> Result<S::Ok, S::Error> {
+        let strings: Vec<String> = hands
+            .iter()
+            .map(|hand| super::cards_to_string(hand))
+            .collect();
+        strings.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<Card>>, D::Error> {
+        let strings = Vec::<String>::deserialize(deserializer)?;
+        strings
+            .iter()
+            .map(|string| super::cards_from_string(string).map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
+// One string of cards per player, None for the unknown hands of a spot
+pub mod optional_hands {
+    use poker::Card;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        hands: &[Option<Vec<Card>>],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let strings: Vec<Option<String>> = hands
+            .iter()
+            .map(|hand| hand.as_ref().map(|hand| super::cards_to_string(hand)))
+            .collect();
+        strings.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Option<Vec<Card>>>, D::Error> {
+        let strings = Vec::<Option<String>>::deserialize(deserializer)?;
+        strings
+            .iter()
+            .map(|string| {
+                string
+                    .as_ref()
+                    .map(|string| super::cards_from_string(string))
+                    .transpose()
+                    .map_err(serde::de::Error::custom)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::action::{ActionConfig, BettingStructure, RaiseSize, Rake, StartingStacks};
+    use crate::game::deal::Deal;
+    use crate::game::environment::Environment;
+    use crate::game::game_variant::GameVariant;
+    use crate::game::hand_state::HandState;
+    use crate::game::spot::Spot;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // Most game types have no PartialEq, a value decoded then encoded again must give the same
+    // JSON and the same bytes
+    fn assert_round_trip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+        let json = to_json(value).unwrap();
+        let from_json_value: T = from_json(&json).unwrap();
+        assert_eq!(to_json(&from_json_value).unwrap(), json);
+
+        let bytes = to_binary(value).unwrap();
+        let from_binary_value: T = from_binary(&bytes).unwrap();
+        assert_eq!(to_binary(&from_binary_value).unwrap(), bytes);
+        from_binary_value
+    }
+
+    #[test]
+    fn action_configs_round_trip() {
+        let mut action_config = ActionConfig::new(6, 1000, 20, 9);
+        action_config.starting_stacks = StartingStacks::Uniform {
+            min: 500,
+            max: 1500,
+        };
+        action_config.preflop_raise_sizes = vec![RaiseSize::LastBet(2.5), RaiseSize::BigBlind(3.0)];
+        action_config.flop_raise_sizes = vec![RaiseSize::Pot(0.33), RaiseSize::Pot(1.0)];
+        action_config.payouts = vec![0.65, 0.35];
+        action_config.rake = Rake::new(5.0, Some(60), true);
+        assert_round_trip(&action_config);
+
+        action_config.game_variant = GameVariant::Omaha;
+        action_config.starting_stacks = StartingStacks::Fixed(vec![1000, 800, 600, 400, 200, 100]);
+        action_config.betting_structure = BettingStructure::FixedLimit {
+            small_bet: 20,
+            big_bet: 40,
+            raise_cap: 4,
+        };
+        assert_round_trip(&action_config);
+    }
+
+    #[test]
+    fn spots_round_trip() {
+        let mut spot = Spot::new(3);
+        spot.stacks = Some(vec![1000, 600, 1400]);
+        spot.hands[1] = Some(cards_from_string("AsKd").unwrap());
+        spot.board = cards_from_string("Qs7h2c").unwrap();
+        spot.actions = vec![1, 1, 1];
+
+        // Cards are readable in JSON
+        let json = to_json(&spot).unwrap();
+        assert!(json.contains("\"AsKd\""));
+        assert!(json.contains("\"Qs7h2c\""));
+
+        let decoded = assert_round_trip(&spot);
+        assert_eq!(decoded.hands, spot.hands);
+        assert_eq!(decoded.board, spot.board);
+
+        // Invalid cards are an error, not a panic
+        assert!(from_json::<Spot>(&json.replace("AsKd", "AsKx")).is_err());
+    }
+
+    #[test]
+    fn deals_and_hand_states_round_trip() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for game_variant in [GameVariant::Holdem, GameVariant::Omaha, GameVariant::Leduc] {
+            let deal = Deal::new(game_variant, 3, &mut rng);
+            let decoded = assert_round_trip(&deal);
+            assert_eq!(decoded.hands, deal.hands);
+            assert_eq!(decoded.board, deal.board);
+        }
+
+        // Hand state after a few actions, with its action states
+        let mut environment = Environment::new(ActionConfig::new(3, 1000, 20, 9), 0).unwrap();
+        environment.step(1).unwrap();
+        environment.step(1).unwrap();
+        let hand_state = environment.observe(1).hand_state;
+        assert_eq!(hand_state.action_states.len(), 2);
+        let decoded: HandState = assert_round_trip(&hand_state);
+        assert_eq!(decoded.hand, hand_state.hand);
+        assert_eq!(decoded.board, hand_state.board);
+        assert_eq!(decoded.action_states.len(), 2);
+    }
+}
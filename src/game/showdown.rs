@@ -0,0 +1,359 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use super::action::{ActionConfig, AllInReward};
+use super::deal::Deal;
+use super::game_error::GameError;
+use super::game_state::{GameState, StateType};
+use super::game_variant::HandRank;
+use super::icm;
+use itertools::Itertools;
+use poker::{Card, Evaluator};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+// Outcome of a finished hand
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HandResult {
+    // Rewards the agents are trained on, not chips with equity or ICM rewards
+    pub rewards: Vec<f32>,
+    // Chips won or lost by each player on the board dealt, net of rake
+    pub chip_results: Vec<i32>,
+}
+
+struct Pot {
+    amount: u32,
+    eligible_players: Vec<u32>,
+}
+
+// Runouts of an all-in before the flop with exact equity, enumerating them all would take over a
+// million evaluations per hand
+const PREFLOP_EQUITY_SAMPLE_COUNT: u32 = 1000;
+
+impl GameState {
+    // Pays the pots of a terminal state, with the cards of the deal
+    pub fn get_hand_result(
+        &self,
+        action_config: &ActionConfig,
+        deal: &Deal,
+    ) -> Result<HandResult, GameError> {
+        if self.state_type != StateType::Terminal {
+            return Err(GameError::Unsupported {
+                state: self.get_type_name(),
+                method: "get_hand_result",
+            });
+        }
+
+        let player_count = self.player_count as usize;
+        let mut pots = self.create_pots();
+
+        // The house takes the rake from the main pot first
+        let mut rake = action_config.rake.get_rake(self);
+        for pot in pots.iter_mut() {
+            let taken = rake.min(pot.amount);
+            pot.amount -= taken;
+            rake -= taken;
+        }
+
+        // Chips are exact, rewards only become floats for training
+        let evaluator = Evaluator::new();
+        let chip_results = self.get_chip_results(&evaluator, deal, &pots, &deal.board)?;
+        let rewards = match self.get_runout_boards(action_config, deal) {
+            None => self.get_training_rewards(action_config, &chip_results),
+            Some(boards) => {
+                // Rewards are averaged over the runouts
+                let mut rewards = vec![0.0; player_count];
+                let mut board_count = 0;
+                for board in boards {
+                    let board_chip_results =
+                        self.get_chip_results(&evaluator, deal, &pots, &board)?;
+                    let board_rewards =
+                        self.get_training_rewards(action_config, &board_chip_results);
+                    for i in 0..player_count {
+                        rewards[i] += board_rewards[i];
+                    }
+                    board_count += 1;
+                }
+                rewards
+                    .iter()
+                    .map(|reward| reward / board_count as f32)
+                    .collect()
+            }
+        };
+
+        Ok(HandResult {
+            rewards,
+            chip_results,
+        })
+    }
+
+    // Chips won or lost by each player if the hand is shown down on this board
+    fn get_chip_results(
+        &self,
+        evaluator: &Evaluator,
+        deal: &Deal,
+        pots: &[Pot],
+        board: &[Card],
+    ) -> Result<Vec<i32>, GameError> {
+        // Remove bets as they are considered as lost
+        let mut chip_results: Vec<i32> = (0..self.player_count)
+            .map(|i| -(self.get_contribution(i) as i32))
+            .collect();
+
+        // Evaluate hands, only needed when more than one player reaches showdown
+        let game_variant = deal.game_variant;
+        let mut evals: Vec<HandRank> =
+            vec![game_variant.get_worst_hand_rank(); self.player_count as usize];
+        if self.players_in_hand > 1 {
+            for i in 0..self.player_count {
+                if self.is_player_in[i as usize] {
+                    evals[i as usize] =
+                        game_variant.evaluate(evaluator, &deal.hands[i as usize], board)?;
+                }
+            }
+        }
+
+        // Give each pot to the best eligible hand(s)
+        for pot in pots {
+            // Get best hand
+            let mut best_hand = evals[pot.eligible_players[0] as usize];
+            for i in pot.eligible_players.iter() {
+                if evals[*i as usize] > best_hand {
+                    best_hand = evals[*i as usize];
+                }
+            }
+
+            // Get players with the best hand (there could be a draw), in seat order from the
+            // left of the button
+            let player_count = self.player_count;
+            let mut indices_with_best_hand: Vec<u32> = pot
+                .eligible_players
+                .iter()
+                .filter(|i| evals[**i as usize] == best_hand)
+                .copied()
+                .collect();
+            indices_with_best_hand
+                .sort_by_key(|i| (i + player_count - self.button - 1) % player_count);
+
+            // Split the pot, the odd chips go one by one to the first winners left of the button
+            let winner_count = indices_with_best_hand.len() as u32;
+            let odd_chips = (pot.amount % winner_count) as usize;
+            for (j, i) in indices_with_best_hand.iter().enumerate() {
+                chip_results[*i as usize] +=
+                    (pot.amount / winner_count) as i32 + if j < odd_chips { 1 } else { 0 };
+            }
+        }
+
+        Ok(chip_results)
+    }
+
+    // Rewards the agents are trained on. Tournament hands are played for prize equity, not for
+    // chips.
+    fn get_training_rewards(&self, action_config: &ActionConfig, chip_results: &[i32]) -> Vec<f32> {
+        let rewards: Vec<f32> = chip_results.iter().map(|chips| *chips as f32).collect();
+        if action_config.payouts.is_empty() {
+            return rewards;
+        }
+
+        let starting_stacks: Vec<u32> = (0..self.player_count)
+            .map(|i| self.get_starting_stack(i))
+            .collect();
+        icm::get_icm_rewards(&starting_stacks, &rewards, &action_config.payouts)
+    }
+
+    // With equity rewards, boards where the cards dealt after the action closed are replaced by
+    // every possible runout, or by random ones. Exact equity is sampled too when no board card
+    // is known. None if the board dealt is played out.
+    fn get_runout_boards<'b>(
+        &self,
+        action_config: &ActionConfig,
+        deal: &'b Deal,
+    ) -> Option<Box<dyn Iterator<Item = Vec<Card>> + 'b>> {
+        let game_variant = deal.game_variant;
+        let street_count = game_variant.get_street_count();
+
+        // The action closed on the street of the last action, preflop if the forced bets put
+        // everyone all-in
+        let closing_street = self.last_action_street.max(1);
+        let known_card_count = game_variant.get_board_card_count(closing_street);
+        let runout_card_count = game_variant.get_board_card_count(street_count) - known_card_count;
+        if action_config.all_in_reward == AllInReward::Runout
+            || self.players_in_hand < 2
+            || runout_card_count == 0
+        {
+            return None;
+        }
+
+        // The hole cards of every player are out of the deck, folded ones included
+        let known_board = &deal.board[..known_card_count];
+        let deck: Vec<Card> = Deal::create_deck(game_variant)
+            .into_iter()
+            .filter(|card| {
+                !known_board.contains(card) && !deal.hands.iter().any(|hand| hand.contains(card))
+            })
+            .collect();
+
+        let create_board = move |runout: &[Card]| {
+            let mut board = known_board.to_vec();
+            board.extend_from_slice(runout);
+            board
+        };
+        let sample_count = match action_config.all_in_reward {
+            AllInReward::SampledEquity(sample_count) => Some(sample_count),
+            AllInReward::Equity if known_card_count == 0 => Some(PREFLOP_EQUITY_SAMPLE_COUNT),
+            _ => None,
+        };
+        match sample_count {
+            Some(sample_count) => {
+                // Seeded by the deal, so the rewards of a hand do not change between calls
+                let mut hasher = DefaultHasher::new();
+                deal.board.hash(&mut hasher);
+                deal.hands.hash(&mut hasher);
+                let mut rng = StdRng::seed_from_u64(hasher.finish());
+
+                Some(Box::new((0..sample_count).map(move |_| {
+                    let runout: Vec<Card> = deck
+                        .choose_multiple(&mut rng, runout_card_count)
+                        .copied()
+                        .collect();
+                    create_board(&runout)
+                })))
+            }
+            None => Some(Box::new(
+                deck.into_iter()
+                    .combinations(runout_card_count)
+                    .map(move |runout| create_board(&runout)),
+            )),
+        }
+    }
+
+    // Split the chips into a main pot and side pots. Each pot layer goes up to the contribution of a player
+    // and can only be won by players still in hand who put at least that amount in.
+    fn create_pots(&self) -> Vec<Pot> {
+        // Antes are part of the pots like any other chip put in
+        let contributions: Vec<u32> = (0..self.player_count)
+            .map(|i| self.get_contribution(i))
+            .collect();
+
+        let mut levels: Vec<u32> = contributions.iter().filter(|c| **c > 0).copied().collect();
+        levels.sort_unstable();
+        levels.dedup();
+
+        let mut pots: Vec<Pot> = Vec::new();
+        let mut dead_chips: u32 = 0;
+        let mut previous_level: u32 = 0;
+
+        for level in levels {
+            let mut amount: u32 = 0;
+            for contribution in contributions.iter() {
+                amount += (*contribution).min(level) - (*contribution).min(previous_level);
+            }
+            previous_level = level;
+
+            let eligible_players: Vec<u32> = (0..self.player_count)
+                .filter(|i| self.is_player_in[*i as usize] && contributions[*i as usize] >= level)
+                .collect();
+
+            if eligible_players.is_empty() {
+                // Only folded players put chips in this layer, they go to the previous pot
+                match pots.last_mut() {
+                    Some(pot) => pot.amount += amount,
+                    None => dead_chips += amount,
+                }
+            } else {
+                pots.push(Pot {
+                    amount: amount + dead_chips,
+                    eligible_players,
+                });
+                dead_chips = 0;
+            }
+        }
+
+        pots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::action::Rake;
+    use crate::game::game_variant::GameVariant;
+    use crate::game::serialization::cards_from_string;
+
+    // Royal flush on the board, every player left splits the pots
+    fn create_split_deal(player_count: usize) -> Deal {
+        let hands = ["2c3d", "2d3c", "2h4c", "4d5c"];
+        Deal {
+            game_variant: GameVariant::Holdem,
+            board: cards_from_string("AsKsQsJsTs").unwrap(),
+            hands: hands[..player_count]
+                .iter()
+                .map(|hand| cards_from_string(hand).unwrap())
+                .collect(),
+        }
+    }
+
+    // The last seat is on the button, the actions are played then everybody checks to showdown
+    fn play_to_showdown(action_config: &ActionConfig, actions: &[usize]) -> GameState {
+        let player_count = action_config.player_count;
+        let mut state = GameState::new(
+            player_count,
+            &vec![action_config.buy_in; player_count as usize],
+            player_count - 1,
+        );
+        let mut actions = actions.iter();
+        while state.state_type != StateType::Terminal {
+            state = if state.state_type == StateType::Chance {
+                state.deal_next_street(action_config).unwrap()
+            } else {
+                let action = actions.next().copied().unwrap_or(1);
+                state.apply_action(action_config, action).unwrap().0
+            };
+        }
+        state
+    }
+
+    #[test]
+    fn odd_chip_goes_to_the_first_winner_left_of_the_button() {
+        let action_config = ActionConfig::new(4, 1000, 20, 9);
+
+        // UTG and the button limp, the small blind folds: 70 chips split 3 ways
+        let state = play_to_showdown(&action_config, &[1, 1, 0]);
+        let deal = create_split_deal(4);
+        let hand_result = state.get_hand_result(&action_config, &deal).unwrap();
+        assert_eq!(hand_result.chip_results, vec![-10, 4, 3, 3]);
+    }
+
+    #[test]
+    fn odd_chip_goes_to_the_big_blind_heads_up() {
+        let mut action_config = ActionConfig::new(2, 1000, 20, 9);
+        action_config.rake = Rake::new(5.0, Some(1), false);
+
+        // The button is the small blind, it acts first preflop but last after the flop. The
+        // rake leaves 39 chips.
+        let state = play_to_showdown(&action_config, &[]);
+        let deal = create_split_deal(2);
+        let hand_result = state.get_hand_result(&action_config, &deal).unwrap();
+        assert_eq!(hand_result.chip_results, vec![0, -1]);
+    }
+
+    #[test]
+    fn exact_equity_is_sampled_before_the_flop() {
+        let mut action_config = ActionConfig::new(2, 1000, 20, 9);
+        action_config.all_in_reward = AllInReward::Equity;
+        let deal = create_split_deal(2);
+
+        // All-in and call preflop
+        let state = play_to_showdown(&action_config, &[2, 2]);
+        let boards = state.get_runout_boards(&action_config, &deal).unwrap();
+        assert_eq!(boards.count(), PREFLOP_EQUITY_SAMPLE_COUNT as usize);
+
+        // All-in and call on the flop, every turn and river of the 45 cards left
+        let state = play_to_showdown(&action_config, &[1, 1, 2, 2]);
+        let boards = state.get_runout_boards(&action_config, &deal).unwrap();
+        assert_eq!(boards.count(), 45 * 44 / 2);
+        assert!(state.get_hand_result(&action_config, &deal).is_ok());
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashSet;
+
+use poker::Card;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use super::action::ActionConfig;
+use super::deal::Deal;
+use super::game_state::GameState;
+use serde::{Deserialize, Serialize};
+
+// Description of a hand situation to start the tree from. Unknown cards are dealt at random,
+// actions are the indexes of the action slots (same as ActionState.action_taken_index).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Spot {
+    pub player_count: u32,
+    pub button: u32,
+    // Stacks at the start of the hand, the action config stacks are used if None
+    pub stacks: Option<Vec<u32>>,
+    // Hole cards of each player, None for cards dealt at random
+    #[serde(with = "super::serialization::optional_hands")]
+    pub hands: Vec<Option<Vec<Card>>>,
+    // Known board cards (up to the whole board), the rest is dealt at random
+    #[serde(with = "super::serialization::cards")]
+    pub board: Vec<Card>,
+    // Actions taken since the start of the hand, replayed before the tree is played
+    pub actions: Vec<usize>,
+}
+
+impl Spot {
+    pub fn new(player_count: u32) -> Spot {
+        Spot {
+            player_count,
+            button: player_count - 1,
+            stacks: None,
+            hands: vec![None; player_count as usize],
+            board: Vec::new(),
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn validate(&self, action_config: &ActionConfig) -> Result<(), Box<dyn std::error::Error>> {
+        if self.player_count != action_config.player_count {
+            return Err(format!(
+                "Spot has {} players but the action config has {}",
+                self.player_count, action_config.player_count
+            )
+            .into());
+        }
+
+        if self.button >= self.player_count {
+            return Err(format!("Invalid button: {}", self.button).into());
+        }
+
+        if let Some(stacks) = &self.stacks {
+            if stacks.len() != self.player_count as usize {
+                return Err("One starting stack is needed per player".into());
+            }
+            if stacks
+                .iter()
+                .any(|s| *s <= action_config.get_max_forced_bet())
+            {
+                return Err("Starting stacks must be larger than the blinds and antes".into());
+            }
+        }
+
+        if self.hands.len() != self.player_count as usize {
+            return Err("One hand (or None) is needed per player".into());
+        }
+
+        let game_variant = action_config.game_variant;
+        let board_card_count = game_variant.get_board_card_count(game_variant.get_street_count());
+        if self.board.len() > board_card_count {
+            return Err(format!("The board has more than {} cards", board_card_count).into());
+        }
+
+        // Make sure no card is used twice
+        let mut cards = HashSet::new();
+        for card in self
+            .hands
+            .iter()
+            .flatten()
+            .flatten()
+            .chain(self.board.iter())
+        {
+            if !game_variant.is_card_used(card) {
+                return Err(format!(
+                    "Card {} is not in the {:?} deck",
+                    card.rank_suit_string(),
+                    game_variant
+                )
+                .into());
+            }
+            if !cards.insert(*card) {
+                return Err(format!("Card {} is used twice", card.rank_suit_string()).into());
+            }
+        }
+
+        let hole_card_count = game_variant.get_hole_card_count();
+        for hand in self.hands.iter().flatten() {
+            if hand.len() != hole_card_count {
+                return Err(format!("A hand must have {} cards", hole_card_count).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    // Deal the cards of the spot, unknown cards come from the shuffled remaining deck. The game
+    // state is the start of the hand, the actions of the spot are not played yet.
+    pub fn create_hand(
+        &self,
+        action_config: &ActionConfig,
+        rng: &mut StdRng,
+    ) -> Result<(Deal, GameState), Box<dyn std::error::Error>> {
+        self.validate(action_config)?;
+
+        let known_cards: HashSet<Card> = self
+            .hands
+            .iter()
+            .flatten()
+            .flatten()
+            .chain(self.board.iter())
+            .copied()
+            .collect();
+
+        let game_variant = action_config.game_variant;
+        let mut remaining: Vec<Card> = Deal::create_deck(game_variant)
+            .into_iter()
+            .filter(|card| !known_cards.contains(card))
+            .collect();
+        remaining.shuffle(rng);
+
+        // Deck order is the board, then the hands of each player
+        let board_card_count = game_variant.get_board_card_count(game_variant.get_street_count());
+        let mut deck = self.board.clone();
+        deck.extend(remaining.drain(..board_card_count - self.board.len()));
+        for hand in self.hands.iter() {
+            match hand {
+                Some(cards) => deck.extend(cards.iter()),
+                None => deck.extend(remaining.drain(..game_variant.get_hole_card_count())),
+            }
+        }
+
+        let stacks = match &self.stacks {
+            Some(stacks) => stacks.clone(),
+            None => action_config.create_starting_stacks(rng)?,
+        };
+
+        Ok((
+            Deal::from_deck(game_variant, self.player_count, deck),
+            GameState::new(self.player_count, &stacks, self.button),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use poker::{Rank, Suit};
+    use rand::SeedableRng;
+
+    fn create_spot() -> Spot {
+        let mut spot = Spot::new(3);
+        spot.hands[1] = Some(vec![
+            Card::new(Rank::Ace, Suit::Spades),
+            Card::new(Rank::King, Suit::Spades),
+        ]);
+        spot.board = vec![
+            Card::new(Rank::Queen, Suit::Spades),
+            Card::new(Rank::Jack, Suit::Spades),
+            Card::new(Rank::Two, Suit::Hearts),
+        ];
+        spot
+    }
+
+    #[test]
+    fn known_cards_are_dealt() {
+        let action_config = ActionConfig::new(3, 1000, 20, 9);
+        let spot = create_spot();
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let (deal, state) = spot.create_hand(&action_config, &mut rng).unwrap();
+            assert_eq!(deal.hands[1], *spot.hands[1].as_ref().unwrap());
+            assert_eq!(deal.board[..3], spot.board[..]);
+            assert_eq!(state.button, spot.button);
+
+            let cards: HashSet<Card> = deal
+                .hands
+                .iter()
+                .flatten()
+                .chain(deal.board.iter())
+                .copied()
+                .collect();
+            assert_eq!(cards.len(), 3 * 2 + 5);
+        }
+    }
+
+    #[test]
+    fn invalid_spots_are_rejected() {
+        let action_config = ActionConfig::new(3, 1000, 20, 9);
+        assert!(create_spot().validate(&action_config).is_ok());
+
+        let mut spot = create_spot();
+        spot.hands[0] = Some(spot.board[..2].to_vec());
+        assert!(spot.validate(&action_config).is_err());
+
+        let mut spot = create_spot();
+        spot.button = 3;
+        assert!(spot.validate(&action_config).is_err());
+
+        let mut spot = create_spot();
+        spot.stacks = Some(vec![1000, 1000]);
+        assert!(spot.validate(&action_config).is_err());
+
+        assert!(create_spot()
+            .validate(&ActionConfig::new(4, 1000, 20, 9))
+            .is_err());
+    }
+}
@@ -0,0 +1,99 @@
+use super::action::{Action, ActionConfig};
+use super::game_error::GameError;
+use super::game_state::{GameState, StateType};
+
+// State of the betting tree, its children are indexes in the arena
+#[derive(Clone, Debug)]
+pub struct StateNode {
+    pub state: GameState,
+    // Action that led from the parent to this state, None for the root and after a deal
+    pub action: Option<Action>,
+    // Only filled for play states, when they are expanded
+    pub valid_actions_mask: Vec<bool>,
+    // One child per action slot for play states (None for invalid actions), a single one for
+    // chance states. Empty until the node is expanded.
+    pub children: Vec<Option<usize>>,
+}
+
+// Betting tree stored in a single vector. The betting does not depend on the cards, so one
+// arena serves every deal, and walking it again does not allocate any state.
+pub struct StateArena {
+    pub nodes: Vec<StateNode>,
+}
+
+impl StateArena {
+    pub fn new(root: GameState) -> StateArena {
+        StateArena {
+            nodes: vec![StateNode {
+                state: root,
+                action: None,
+                valid_actions_mask: Vec::new(),
+                children: Vec::new(),
+            }],
+        }
+    }
+
+    // Create the children of a node, nothing is done if they already exist
+    pub fn expand(&mut self, index: usize, action_config: &ActionConfig) -> Result<(), GameError> {
+        let node = self.nodes.get(index).ok_or(GameError::MissingState)?;
+        if !node.children.is_empty() {
+            return Ok(());
+        }
+
+        let state = node.state;
+        match state.state_type {
+            StateType::Terminal => {
+                return Err(GameError::Unsupported {
+                    state: "terminal",
+                    method: "expand",
+                })
+            }
+            StateType::Chance => {
+                let child = self.push(state.deal_next_street(action_config)?, None);
+                self.nodes[index].children = vec![Some(child)];
+            }
+            StateType::Play => {
+                let valid_actions_mask = state.get_valid_actions_mask(action_config)?;
+                let mut children = Vec::with_capacity(valid_actions_mask.len());
+                for (action_index, valid) in valid_actions_mask.iter().enumerate() {
+                    if *valid {
+                        let (child, action) = state.apply_action(action_config, action_index)?;
+                        children.push(Some(self.push(child, Some(action))));
+                    } else {
+                        children.push(None);
+                    }
+                }
+                self.nodes[index].valid_actions_mask = valid_actions_mask;
+                self.nodes[index].children = children;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Index of the child reached with an action slot (0 for a chance state), the node must be
+    // expanded
+    pub fn get_child(&self, index: usize, action_index: usize) -> Result<usize, GameError> {
+        let node = self.nodes.get(index).ok_or(GameError::MissingState)?;
+        match node.children.get(action_index) {
+            Some(Some(child)) => Ok(*child),
+            _ if node.state.state_type == StateType::Play && !node.children.is_empty() => {
+                Err(GameError::InvalidAction {
+                    player: node.state.player_to_move,
+                    action_index,
+                })
+            }
+            _ => Err(GameError::MissingState),
+        }
+    }
+
+    fn push(&mut self, state: GameState, action: Option<Action>) -> usize {
+        self.nodes.push(StateNode {
+            state,
+            action,
+            valid_actions_mask: Vec::new(),
+            children: Vec::new(),
+        });
+        self.nodes.len() - 1
+    }
+}
@@ -1,15 +1,19 @@
 use std::sync::{Arc, Mutex};
 
 use candle_core::Tensor;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use threadpool::ThreadPool;
 
-use super::action::ActionConfig;
+use super::action::{Action, ActionConfig};
 use super::action_state::ActionState;
+use super::deal::Deal;
+use super::game_error::GameError;
+use super::game_state::{GameState, StateType};
 use super::hand_state::HandState;
-use super::state::{State, StateType};
-use super::state_chance::StateChance;
-use super::state_data::StateData;
+use super::invariants;
+use super::showdown::HandResult;
+use super::spot::Spot;
 use crate::agent::Agent;
 use crate::model::poker_network::PokerNetwork;
 use colored::*;
@@ -17,239 +21,253 @@ use colored::*;
 pub struct Tree<'a> {
     player_cnt: u32,
     action_config: &'a ActionConfig,
-    root: Option<Box<dyn State<'a> + 'a>>,
+    // State the hand starts from, after the actions of the spot if there is one
+    root: Option<GameState>,
+    deal: Option<Deal>,
+    // Actions taken to reach the root
+    history: Vec<Action>,
+    button: u32,
+    // Used for dealing and agent decisions, the same seed plays the same hands
+    rng: StdRng,
+    // If set, every hand starts from this spot instead of a new deal
+    spot: Option<Spot>,
+    // If set, hands start with these stacks instead of the configured starting stacks
+    stacks: Option<Vec<u32>>,
     pub hand_state: Option<HandState>,
+    // View of the hand for each seat, only its own hole cards are known
+    hand_states: Vec<HandState>,
+    // Validation mode, every transition is checked against the rules of the game
+    check_invariants: bool,
+}
+
+// What stays the same during the traversal of a hand
+struct Traversal<'b> {
+    traverser: u32,
+    deal: &'b Deal,
+    agents: &'b [Arc<Box<dyn Agent>>],
+    action_config: &'b ActionConfig,
+    device: &'b candle_core::Device,
+    no_invalid_for_traverser: bool,
+    epsilon_greedy: f32,
+    check_invariants: bool,
 }
 
 impl<'a> Tree<'a> {
-    pub fn new(player_cnt: u32, action_config: &ActionConfig) -> Tree {
+    pub fn new(player_cnt: u32, action_config: &'a ActionConfig, seed: u64) -> Tree<'a> {
         Tree {
             player_cnt,
             action_config,
             root: None,
+            deal: None,
+            history: Vec::new(),
+            // Last seat is on the button, so seat 0 posts the small blind (the button does heads-up)
+            button: player_cnt - 1,
+            rng: StdRng::seed_from_u64(seed),
+            spot: None,
+            stacks: None,
             hand_state: None,
+            hand_states: Vec::new(),
+            check_invariants: false,
         }
     }
 
-    fn reset(&mut self, traverser: u32) {
-        // Shuffle the deck
-        self.root = Some(Box::new(StateChance::new(
-            self.action_config,
-            StateData::new(self.player_cnt, self.action_config.buy_in),
-        )));
-
-        self.hand_state = Some(HandState {
-            traverser,
-            hand: self.root.as_ref().unwrap().get_state_data().hands[traverser as usize].clone(),
-            board: self.root.as_ref().unwrap().get_state_data().board.clone(),
-            action_states: Vec::new(),
-        });
+    // Slower, but a broken rule is returned as an error at the transition that broke it
+    pub fn set_check_invariants(&mut self, check_invariants: bool) {
+        self.check_invariants = check_invariants;
     }
 
-    pub fn traverse(
-        &mut self,
-        traverser: u32,
-        agents: &Vec<Arc<Box<dyn Agent>>>,
-        device: &candle_core::Device,
-        no_invalid_for_traverser: bool,
-        epsilon_greedy: f32,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        self.reset(traverser);
+    pub fn set_button(&mut self, button: u32) {
+        self.button = button % self.player_cnt;
+    }
 
-        Tree::traverse_state(
-            &mut self.root,
-            self.hand_state.as_mut().unwrap(),
-            agents,
-            self.action_config,
-            device,
-            no_invalid_for_traverser,
-            epsilon_greedy,
-        )?;
-        // println!(
-        //     "Action states length: {}",
-        //     self.hand_state.as_ref().unwrap().action_states.len()
-        // );
+    // Move the button to the next seat, to be called between consecutive hands. Seats sitting
+    // out are skipped.
+    pub fn move_button(&mut self) {
+        let player_cnt = self.player_cnt;
+        let button = self.button;
+        self.button = (1..=player_cnt)
+            .map(|i| (button + i) % player_cnt)
+            .find(|seat| match &self.stacks {
+                Some(stacks) => stacks[*seat as usize] > 0,
+                None => true,
+            })
+            .unwrap_or(button);
+    }
 
+    pub fn set_spot(&mut self, spot: Option<Spot>) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(spot) = &spot {
+            spot.validate(self.action_config)?;
+        }
+        self.spot = spot;
         Ok(())
     }
 
-    fn traverse_state(
-        state_option: &mut Option<Box<dyn State<'a> + 'a>>,
-        hand_state: &mut HandState,
-        agents: &Vec<Arc<Box<dyn Agent>>>,
-        action_config: &ActionConfig,
-        device: &candle_core::Device,
-        no_invalid_for_traverser: bool,
-        epsilon_greedy: f32,
+    // Stacks of the next hands, to carry them over from one hand to the next. Seats with an empty
+    // stack sit out.
+    pub fn set_stacks(
+        &mut self,
+        stacks: Option<Vec<u32>>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        // If state is None, panic
-        if state_option.is_none() {
-            panic!("State is None");
+        if let Some(stacks) = &stacks {
+            if stacks.len() != self.player_cnt as usize {
+                return Err("One stack is needed per player".into());
+            }
+            // Short stacks post what they have and are all-in
+            if stacks.iter().filter(|stack| **stack > 0).count() < 2 {
+                return Err("At least two stacks must not be empty".into());
+            }
         }
+        self.stacks = stacks;
+        Ok(())
+    }
 
-        // // Make sure we do not have too much actions in hand_state
-        // {
-        //     let mut action_cnt = 0;
-        //     let mut too_much = false;
-        //     let mut street = 0;
-        //     for action_state in hand_state.action_states.iter() {
-        //         if action_state.street == street {
-        //             action_cnt += 1;
-        //         } else {
-        //             street = action_state.street;
-        //             action_cnt = 0;
-        //         }
-
-        //         if action_cnt >= action_config.max_actions_per_street {
-        //             too_much = true;
-        //             break;
-        //         }
-        //     }
-
-        //     if too_much {
-        //         return Err("Too much actions".into());
-        //     }
-        // }
-
-        let state = state_option.as_mut().unwrap();
-        let traverser = hand_state.traverser;
-
-        if matches!(state.get_type(), StateType::Terminal) {
-            // Use reward from terminal state. We may have no action states if every player folded
-            // except the traverser in BB
-            Self::update_last_traverser_reward(hand_state, state.get_reward(traverser), false);
-        } else if !state.is_player_in_hand(traverser) {
-            // Use the negative of his bet as reward
-            Self::update_last_traverser_reward(
-                hand_state,
-                -(state.get_state_data().bets[traverser as usize] as f32),
-                false,
-            );
-        } else if matches!(state.get_type(), StateType::Chance) {
-            // Create children
-            state.create_children();
-
-            // Traverse first child
-            return Self::traverse_state(
-                state.get_child(0),
-                hand_state,
-                agents,
-                action_config,
-                device,
-                no_invalid_for_traverser,
-                epsilon_greedy,
-            );
-        } else {
-            // Traverse for player to move
-            state.create_children();
+    fn reset(&mut self, traverser: u32, button: u32) -> Result<(), Box<dyn std::error::Error>> {
+        // Shuffle the deck
+        let (deal, mut state) = match &self.spot {
+            Some(spot) => spot.create_hand(self.action_config, &mut self.rng)?,
+            None => {
+                let stacks = match &self.stacks {
+                    Some(stacks) => stacks.clone(),
+                    None => self.action_config.create_starting_stacks(&mut self.rng)?,
+                };
+                let deal = Deal::new(
+                    self.action_config.game_variant,
+                    self.player_cnt,
+                    &mut self.rng,
+                );
+                (deal, GameState::new(self.player_cnt, &stacks, button))
+            }
+        };
+        self.hand_states = (0..self.player_cnt)
+            .map(|player| HandState::new(player, &deal, &state))
+            .collect();
+        self.history.clear();
+
+        // Replay the actions of the spot, the tree then starts from the reached state
+        if let Some(spot) = &self.spot {
+            for action_index in spot.actions.iter() {
+                while state.state_type == StateType::Chance {
+                    let child = state.deal_next_street(self.action_config)?;
+                    if self.check_invariants {
+                        invariants::check_transition(
+                            self.action_config,
+                            &state,
+                            0,
+                            &child,
+                            &self.history,
+                            &deal,
+                            &self.hand_states[0].stacks,
+                        )?;
+                    }
+                    state = child;
+                }
 
-            let valid_actions_mask = state.get_valid_actions_mask();
+                if state.state_type == StateType::Terminal {
+                    return Err("Spot has more actions than the hand".into());
+                }
 
-            let mut rng = rand::thread_rng();
-            let random_float_0_1: f32 = rng.gen();
+                let valid_actions_mask = state.get_valid_actions_mask(self.action_config)?;
+                if *action_index >= valid_actions_mask.len() || !valid_actions_mask[*action_index] {
+                    return Err(format!(
+                        "Invalid action {} in spot for player {}",
+                        action_index, state.player_to_move
+                    )
+                    .into());
+                }
 
-            let action_index = if random_float_0_1 >= epsilon_greedy || epsilon_greedy == 0.0 {
-                // Regular traversal, we choose an action from the network
-                agents[state.get_player_to_move() as usize].choose_action(
-                    hand_state,
-                    &valid_actions_mask,
-                    state.get_state_data().street,
-                    action_config,
-                    device,
-                    if state.get_player_to_move() == traverser as i32 {
-                        no_invalid_for_traverser
-                    } else {
-                        true
-                    },
-                )?
-            } else {
-                // Epsilon greedy, we choose a random action to favor exploration
-                let mut index: usize = rng.gen_range(0..valid_actions_mask.len());
-                while (traverser as i32 != state.get_player_to_move() || no_invalid_for_traverser)
-                    && !valid_actions_mask[index]
-                {
-                    index = rng.gen_range(0..valid_actions_mask.len());
+                let (child, action) = state.apply_action(self.action_config, *action_index)?;
+                self.history.push(action);
+                if self.check_invariants {
+                    invariants::check_transition(
+                        self.action_config,
+                        &state,
+                        *action_index,
+                        &child,
+                        &self.history,
+                        &deal,
+                        &self.hand_states[0].stacks,
+                    )?;
                 }
-                index
-            };
 
-            if action_index > valid_actions_mask.len() || !valid_actions_mask[action_index] {
-                if state.get_player_to_move() == traverser as i32 && !no_invalid_for_traverser {
+                for hand_state in self.hand_states.iter_mut() {
                     hand_state.action_states.push(Self::build_action_state(
-                        traverser,
-                        state,
-                        action_index,
-                        true,
+                        hand_state.traverser,
+                        &state,
+                        &valid_actions_mask,
+                        *action_index,
+                        Some(action),
+                        false,
                     ));
-
-                    Self::update_last_traverser_reward(
-                        hand_state,
-                        -((action_config.player_count - 1) as f32 * action_config.buy_in as f32),
-                        true,
-                    );
-                    return Ok(());
-                } else {
-                    panic!("Invalid action index in tree traversal");
                 }
+                state = child;
             }
+        }
 
-            hand_state.action_states.push(Self::build_action_state(
-                traverser,
-                state,
-                action_index,
-                false,
-            ));
+        self.root = Some(state);
+        self.deal = Some(deal);
+        self.hand_state = Some(self.hand_states[traverser as usize].clone());
 
-            Self::traverse_state(
-                state.get_child(action_index),
-                hand_state,
-                agents,
-                action_config,
-                device,
-                no_invalid_for_traverser,
-                epsilon_greedy,
-            )?;
-        }
+        Ok(())
+    }
+
+    pub fn traverse(
+        &mut self,
+        traverser: u32,
+        agents: &[Arc<Box<dyn Agent>>],
+        device: &candle_core::Device,
+        no_invalid_for_traverser: bool,
+        epsilon_greedy: f32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.reset(traverser, self.button)?;
+
+        let root = self.root.ok_or(GameError::MissingState)?;
+        let mut history = self.history.clone();
+        let traversal = Traversal {
+            traverser,
+            deal: self.deal.as_ref().ok_or(GameError::MissingState)?,
+            agents,
+            action_config: self.action_config,
+            device,
+            no_invalid_for_traverser,
+            epsilon_greedy,
+            check_invariants: self.check_invariants,
+        };
+        traversal.traverse_state(root, &mut history, &mut self.hand_states, &mut self.rng)?;
+        self.hand_state = Some(self.hand_states[traverser as usize].clone());
+        // println!(
+        //     "Action states length: {}",
+        //     self.hand_state.as_ref().unwrap().action_states.len()
+        // );
 
         Ok(())
     }
 
-    fn build_action_state(
+    // Action state seen before the action at action_index is taken in state. Invalid actions are
+    // not played, so they have no action taken.
+    pub fn build_action_state(
         traverser: u32,
-        state: &mut Box<dyn State<'a> + 'a>,
+        state: &GameState,
+        valid_actions_mask: &[bool],
         action_index: usize,
+        action_taken: Option<Action>,
         is_invalid: bool,
     ) -> ActionState {
         let mut max_reward: u32 = 0;
-        for i in 0..state.get_player_count() {
+        for i in 0..state.player_count {
             if i != traverser {
-                max_reward += state.get_state_data().bets[i as usize];
+                max_reward += state.get_contribution(i);
             }
         }
 
         ActionState {
-            player_to_move: state.get_player_to_move() as u32,
+            player_to_move: state.player_to_move as u32,
             reward: 0.0,
-            valid_actions_mask: state.get_valid_actions_mask(),
+            valid_actions_mask: valid_actions_mask.to_vec(),
             action_taken_index: action_index,
-            action_taken: if !state.get_child(action_index).is_none() {
-                Some(
-                    state
-                        .get_child(action_index)
-                        .as_ref()
-                        .unwrap()
-                        .get_state_data()
-                        .history
-                        .last()
-                        .unwrap()
-                        .clone(),
-                )
-            } else {
-                None
-            },
+            action_taken,
             is_terminal: false,
-            street: state.get_state_data().street,
-            min_reward: -(state.get_state_data().bets[traverser as usize] as f32),
+            street: state.street,
+            min_reward: -(state.get_contribution(traverser) as f32),
             max_reward: max_reward as f32,
             is_invalid,
         }
@@ -264,15 +282,13 @@ impl<'a> Tree<'a> {
         let n_workers = num_cpus::get();
         let thread_pool = ThreadPool::new(n_workers);
 
-        let action_count = base_action_config.postflop_raise_sizes.len() + 3;
+        let action_count = base_action_config.get_action_count();
         let base_result: Arc<Mutex<Vec<Vec<Vec<f32>>>>> =
             Arc::new(Mutex::new(vec![vec![vec![0.0; 13]; 13]; action_count]));
         let base_count: Arc<Mutex<Vec<Vec<Vec<u32>>>>> =
             Arc::new(Mutex::new(vec![vec![vec![0; 13]; 13]; action_count]));
-        let mut base_valid_actions_mask: Vec<bool> = base_action_config
-            .preflop_raise_sizes
-            .iter()
-            .map(|&x| x > 0.0)
+        let mut base_valid_actions_mask: Vec<bool> = (0..base_action_config.get_raise_slot_count())
+            .map(|i| i < base_action_config.get_street_raise_slot_count(1))
             .collect();
 
         base_valid_actions_mask.insert(0, true);
@@ -299,20 +315,30 @@ impl<'a> Tree<'a> {
                         let rank2: usize = j / 4;
                         let suit2: usize = j % 4;
 
-                        let mut card_vecs: Vec<Vec<Vec<f32>>> = vec![vec![vec![0.0; 13]; 4]; 6];
+                        // Skip the cards that are not in the deck of the variant
+                        let lowest_rank = action_config.game_variant.get_lowest_rank();
+                        if rank1 < lowest_rank || rank2 < lowest_rank {
+                            continue;
+                        }
+
+                        let mut card_vecs: Vec<Vec<Vec<f32>>> =
+                            vec![
+                                vec![vec![0.0; action_config.game_variant.get_rank_count()]; 4];
+                                6
+                            ];
 
                         // Set hand cards
-                        card_vecs[0][suit1][rank1] = 1.0;
-                        card_vecs[0][suit2][rank2] = 1.0;
-                        card_vecs[4][suit1][rank1] = 1.0;
-                        card_vecs[4][suit2][rank2] = 1.0;
+                        card_vecs[0][suit1][rank1 - lowest_rank] = 1.0;
+                        card_vecs[0][suit2][rank2 - lowest_rank] = 1.0;
+                        card_vecs[4][suit1][rank1 - lowest_rank] = 1.0;
+                        card_vecs[4][suit2][rank2 - lowest_rank] = 1.0;
 
                         // Create action tensor
                         // Shape is (street_cnt * max_actions_per_street) x (player_count + 2 for sum and legal) x max_number_of_actions
                         let action_vecs: Vec<Vec<Vec<f32>>> =
                             vec![
                                 vec![
-                                    vec![0.0; 3 + action_config.postflop_raise_sizes.len()];
+                                    vec![0.0; action_config.get_action_count()];
                                     action_config.player_count as usize + 2
                                 ];
                                 4 * action_config.max_actions_per_street as usize
@@ -445,7 +471,7 @@ impl<'a> Tree<'a> {
         Ok(())
     }
 
-    fn update_last_traverser_reward(hand_state: &mut HandState, reward: f32, set_min: bool) {
+    pub fn update_last_traverser_reward(hand_state: &mut HandState, reward: f32, set_min: bool) {
         if let Some(b) = hand_state
             .action_states
             .iter_mut()
@@ -495,98 +521,413 @@ impl<'a> Tree<'a> {
         agents: &[Arc<Box<dyn Agent>>],
         device: &candle_core::Device,
         silent: bool,
-    ) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
-        self.reset(0);
+    ) -> Result<HandResult, Box<dyn std::error::Error>> {
+        self.reset(0, self.button)?;
 
-        let mut gs = self.root.as_mut().unwrap();
+        let mut state = self.root.ok_or(GameError::MissingState)?;
+        let deal = self.deal.as_ref().ok_or(GameError::MissingState)?;
         let mut first = true;
 
-        while !matches!(gs.get_type(), StateType::Terminal) {
-            if matches!(gs.get_type(), StateType::Chance) {
-                gs.create_children();
-                gs = gs.get_child(0).as_mut().unwrap();
+        while state.state_type != StateType::Terminal {
+            if state.state_type == StateType::Chance {
+                let child = state.deal_next_street(self.action_config)?;
+                if self.check_invariants {
+                    invariants::check_transition(
+                        self.action_config,
+                        &state,
+                        0,
+                        &child,
+                        &self.history,
+                        deal,
+                        &self.hand_states[0].stacks,
+                    )?;
+                }
+                state = child;
 
                 if !silent {
                     if first {
                         println!();
                         print!("Player Cards: ");
+                        let positions = &self.hand_state.as_ref().unwrap().positions;
                         for i in 0..self.player_cnt {
-                            let player_cards = gs.get_state_data().hands[i as usize].clone();
-                            print!(
-                                "{}{} ",
-                                player_cards[0].rank_suit_string(),
-                                player_cards[1].rank_suit_string()
-                            );
+                            for card in deal.hands[i as usize].iter() {
+                                print!("{}", card.rank_suit_string());
+                            }
+                            match positions[i as usize] {
+                                Some(position) => print!(" ({}) ", position.to_short_string()),
+                                None => print!(" (out) "),
+                            }
                         }
                         println!();
                         first = false;
-                    } else if gs.get_state_data().street >= 2
-                        && !gs.get_state_data().board.is_empty()
-                    {
+                    } else if state.street >= 2 && !deal.board.is_empty() {
                         print!("Table Cards: ");
-                        let board_cnt = match gs.get_state_data().street {
-                            2 => 3,
-                            3 => 4,
-                            4 => 5,
-                            _ => 0,
-                        };
+                        let board_cnt = self
+                            .action_config
+                            .game_variant
+                            .get_board_card_count(state.street);
 
                         for i in 0..board_cnt {
-                            print!(
-                                "{} ",
-                                gs.get_state_data().board[i as usize].rank_suit_string()
-                            );
+                            print!("{} ", deal.board[i].rank_suit_string());
                         }
                         println!();
                     }
                 }
             } else {
-                let p_to_move = gs.get_player_to_move();
-                gs.create_children();
+                let p_to_move = state.player_to_move;
+                let valid_actions_mask = state.get_valid_actions_mask(self.action_config)?;
 
                 if !silent {
                     print!("Player {}'s turn: ", p_to_move);
                 }
 
+                // Each agent only sees its own cards and the actions taken so far
                 let action_index = agents[p_to_move as usize].as_ref().choose_action(
-                    self.hand_state.as_ref().unwrap(),
-                    &gs.get_valid_actions_mask(),
-                    gs.get_state_data().street,
+                    &self.hand_states[p_to_move as usize],
+                    &valid_actions_mask,
+                    state.street,
                     self.action_config,
                     device,
                     true,
+                    &mut self.rng,
                 )?;
 
-                gs = gs.get_child(action_index).as_mut().unwrap();
+                let (child, action) = state.apply_action(self.action_config, action_index)?;
+                self.history.push(action);
+                if self.check_invariants {
+                    invariants::check_transition(
+                        self.action_config,
+                        &state,
+                        action_index,
+                        &child,
+                        &self.history,
+                        deal,
+                        &self.hand_states[0].stacks,
+                    )?;
+                }
+
+                for hand_state in self.hand_states.iter_mut() {
+                    hand_state.action_states.push(Self::build_action_state(
+                        hand_state.traverser,
+                        &state,
+                        &valid_actions_mask,
+                        action_index,
+                        Some(action),
+                        false,
+                    ));
+                }
+
+                state = child;
 
                 if !silent {
                     print!(
                         "{} ({} {})",
-                        gs.get_state_data()
-                            .history
-                            .last()
-                            .unwrap()
-                            ._to_print_string(),
-                        gs.get_state_data().bets[p_to_move as usize],
-                        gs.get_state_data().stacks[p_to_move as usize],
+                        action._to_print_string(),
+                        state.bets[p_to_move as usize],
+                        state.stacks[p_to_move as usize],
                     );
                     println!();
                 }
             }
         }
+
+        let hand_result = state.get_hand_result(self.action_config, deal)?;
         if !silent {
             println!();
             print!("Rewards: ");
-            for i in 0..self.player_cnt {
-                print!("{} ", gs.get_reward(i));
+            for reward in hand_result.rewards.iter() {
+                print!("{} ", reward);
             }
             println!();
         }
 
-        let rewards = (0..self.player_cnt)
-            .map(|i| gs.get_reward(i))
-            .collect::<Vec<f32>>();
+        for hand_state in self.hand_states.iter_mut() {
+            let reward = hand_result.rewards[hand_state.traverser as usize];
+            Self::update_last_traverser_reward(hand_state, reward, false);
+        }
+
+        Ok(hand_result)
+    }
+}
+
+impl<'b> Traversal<'b> {
+    // The state is copied down the hand, no state is kept once the traversal is done. Each seat
+    // has its own view of the hand, the agents only see their own hole cards.
+    fn traverse_state(
+        &self,
+        state: GameState,
+        history: &mut Vec<Action>,
+        hand_states: &mut [HandState],
+        rng: &mut StdRng,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // // Make sure we do not have too much actions in hand_state
+        // {
+        //     let mut action_cnt = 0;
+        //     let mut too_much = false;
+        //     let mut street = 0;
+        //     for action_state in hand_state.action_states.iter() {
+        //         if action_state.street == street {
+        //             action_cnt += 1;
+        //         } else {
+        //             street = action_state.street;
+        //             action_cnt = 0;
+        //         }
+
+        //         if action_cnt >= action_config.max_actions_per_street {
+        //             too_much = true;
+        //             break;
+        //         }
+        //     }
+
+        //     if too_much {
+        //         return Err("Too much actions".into());
+        //     }
+        // }
+
+        let traverser = self.traverser;
+        let action_config = self.action_config;
+
+        if state.state_type == StateType::Terminal {
+            // Use reward from terminal state. We may have no action states if every player folded
+            // except the traverser in BB
+            let hand_result = state.get_hand_result(action_config, self.deal)?;
+            Tree::update_last_traverser_reward(
+                &mut hand_states[traverser as usize],
+                hand_result.rewards[traverser as usize],
+                false,
+            );
+        } else if !state.is_player_in[traverser as usize] && action_config.payouts.is_empty() {
+            // Use the negative of his bet as reward. With ICM, the reward depends on how the
+            // hand ends for the others, so the hand is played to the end
+            Tree::update_last_traverser_reward(
+                &mut hand_states[traverser as usize],
+                -(state.get_contribution(traverser) as f32),
+                false,
+            );
+        } else if state.state_type == StateType::Chance {
+            // Deal the next street
+            let child = state.deal_next_street(action_config)?;
+            if self.check_invariants {
+                invariants::check_transition(
+                    action_config,
+                    &state,
+                    0,
+                    &child,
+                    history,
+                    self.deal,
+                    &hand_states[traverser as usize].stacks,
+                )?;
+            }
+
+            return self.traverse_state(child, history, hand_states, rng);
+        } else {
+            // Traverse for player to move
+            let valid_actions_mask = state.get_valid_actions_mask(action_config)?;
+            let player_to_move = state.player_to_move;
+
+            let random_float_0_1: f32 = rng.gen();
+
+            let action_index =
+                if random_float_0_1 >= self.epsilon_greedy || self.epsilon_greedy == 0.0 {
+                    // Regular traversal, we choose an action from the network with the view of the
+                    // player to move
+                    self.agents[player_to_move as usize].choose_action(
+                        &hand_states[player_to_move as usize],
+                        &valid_actions_mask,
+                        state.street,
+                        action_config,
+                        self.device,
+                        if player_to_move == traverser as i32 {
+                            self.no_invalid_for_traverser
+                        } else {
+                            true
+                        },
+                        rng,
+                    )?
+                } else {
+                    // Epsilon greedy, we choose a random action to favor exploration
+                    let mut index: usize = rng.gen_range(0..valid_actions_mask.len());
+                    while (traverser as i32 != player_to_move || self.no_invalid_for_traverser)
+                        && !valid_actions_mask[index]
+                    {
+                        index = rng.gen_range(0..valid_actions_mask.len());
+                    }
+                    index
+                };
+
+            if action_index >= valid_actions_mask.len() || !valid_actions_mask[action_index] {
+                if player_to_move == traverser as i32 && !self.no_invalid_for_traverser {
+                    let hand_state = &mut hand_states[traverser as usize];
+                    hand_state.action_states.push(Tree::build_action_state(
+                        traverser,
+                        &state,
+                        &valid_actions_mask,
+                        action_index,
+                        None,
+                        true,
+                    ));
+
+                    let max_loss = hand_state.get_reward_ratio();
+                    Tree::update_last_traverser_reward(hand_state, -max_loss, true);
+                    return Ok(());
+                } else {
+                    return Err(GameError::InvalidAction {
+                        player: player_to_move,
+                        action_index,
+                    }
+                    .into());
+                }
+            }
+
+            let (child, action) = state.apply_action(action_config, action_index)?;
+            history.push(action);
+            if self.check_invariants {
+                invariants::check_transition(
+                    action_config,
+                    &state,
+                    action_index,
+                    &child,
+                    history,
+                    self.deal,
+                    &hand_states[traverser as usize].stacks,
+                )?;
+            }
+
+            for hand_state in hand_states.iter_mut() {
+                hand_state.action_states.push(Tree::build_action_state(
+                    hand_state.traverser,
+                    &state,
+                    &valid_actions_mask,
+                    action_index,
+                    Some(action),
+                    false,
+                ));
+            }
+
+            self.traverse_state(child, history, hand_states, rng)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::agent_random::AgentRandom;
+
+    // Random agent of one seat, fails if it is given the view of another seat
+    struct AgentSeat {
+        seat: u32,
+    }
+
+    impl Agent for AgentSeat {
+        fn choose_action(
+            &self,
+            hand_state: &HandState,
+            valid_actions_mask: &[bool],
+            street: u8,
+            action_config: &ActionConfig,
+            device: &candle_core::Device,
+            no_invalid: bool,
+            rng: &mut StdRng,
+        ) -> Result<usize, Box<dyn std::error::Error>> {
+            if hand_state.traverser != self.seat {
+                return Err(format!(
+                    "Seat {} sees the hand of seat {}",
+                    self.seat, hand_state.traverser
+                )
+                .into());
+            }
+            AgentRandom {}.choose_action(
+                hand_state,
+                valid_actions_mask,
+                street,
+                action_config,
+                device,
+                no_invalid,
+                rng,
+            )
+        }
+
+        fn get_action_probabilities(
+            &self,
+            hand_state: &HandState,
+            valid_actions_mask: &[bool],
+            street: u8,
+            action_config: &ActionConfig,
+            device: &candle_core::Device,
+            no_invalid: bool,
+        ) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+            AgentRandom {}.get_action_probabilities(
+                hand_state,
+                valid_actions_mask,
+                street,
+                action_config,
+                device,
+                no_invalid,
+            )
+        }
+    }
+
+    #[test]
+    fn spot_actions_are_replayed() {
+        let action_config = ActionConfig::new(3, 1000, 20, 9);
+        let mut spot = Spot::new(3);
+        // The button, the small blind and the big blind call preflop
+        spot.actions = vec![1, 1, 1];
+        let mut tree = Tree::new(3, &action_config, 0);
+        tree.set_spot(Some(spot)).unwrap();
+
+        tree.reset(0, 0).unwrap();
+        let root = tree.root.unwrap();
+        assert_eq!(root.state_type, StateType::Chance);
+        assert_eq!(root.get_pot(), 60);
+        assert_eq!(tree.history.len(), 3);
+        for hand_state in tree.hand_states.iter() {
+            let players: Vec<u32> = hand_state
+                .action_states
+                .iter()
+                .map(|action_state| action_state.player_to_move)
+                .collect();
+            assert_eq!(players, vec![2, 0, 1]);
+        }
 
-        Ok(rewards)
+        // Hands then start from the flop
+        let agents: Vec<Arc<Box<dyn Agent>>> = (0..3)
+            .map(|seat| Arc::new(Box::new(AgentSeat { seat }) as Box<dyn Agent>))
+            .collect();
+        for _ in 0..50 {
+            let hand_result = tree
+                .play_one_hand(&agents, &candle_core::Device::Cpu, true)
+                .unwrap();
+            assert_eq!(hand_result.chip_results.iter().sum::<i32>(), 0);
+            assert!(tree.history[3..].iter().all(|action| action.street >= 2));
+        }
+
+        let mut spot = Spot::new(3);
+        spot.actions = vec![0, 0, 1];
+        tree.set_spot(Some(spot)).unwrap();
+        assert!(tree.reset(0, 0).is_err());
+    }
+
+    #[test]
+    fn traverse_gives_each_agent_its_own_hand() {
+        let action_config = ActionConfig::new(4, 1000, 20, 9);
+        let agents: Vec<Arc<Box<dyn Agent>>> = (0..4)
+            .map(|seat| Arc::new(Box::new(AgentSeat { seat }) as Box<dyn Agent>))
+            .collect();
+        let mut tree = Tree::new(4, &action_config, 0);
+        for hand in 0..200 {
+            tree.move_button();
+            tree.traverse(hand % 4, &agents, &candle_core::Device::Cpu, true, 0.0)
+                .unwrap();
+            let hand_state = tree.hand_state.as_ref().unwrap();
+            assert_eq!(hand_state.traverser, hand % 4);
+            assert_eq!(
+                hand_state.hand,
+                tree.deal.as_ref().unwrap().hands[(hand % 4) as usize]
+            );
+        }
     }
 }
@@ -0,0 +1,4 @@
+pub mod agent;
+pub mod game;
+pub mod helper;
+pub mod model;
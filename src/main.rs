@@ -2,20 +2,21 @@
 // use candle_nn::{VarBuilder, VarMap};
 
 use candle_core::Device;
-use model::trainer_config::TrainerConfig;
+use deep_poker::game::action::RaiseSize;
+use deep_poker::model::trainer_config::TrainerConfig;
+use deep_poker::{game, model};
 use std::backtrace::Backtrace;
 use std::env;
 
-mod agent;
-mod game;
-mod helper;
-mod model;
-
 fn main() {
     let mut action_config = game::action::ActionConfig::new(3, 300, 20, 9);
-    // 0.0 values are ignored for raises
-    action_config.preflop_raise_sizes = vec![2.0, 3.0, 0.0, 0.0];
-    action_config.postflop_raise_sizes = vec![0.25, 0.5, 0.66, 1.0];
+    action_config.preflop_raise_sizes = vec![RaiseSize::LastBet(2.0), RaiseSize::LastBet(3.0)];
+    action_config.set_postflop_raise_sizes(vec![
+        RaiseSize::Pot(0.25),
+        RaiseSize::Pot(0.5),
+        RaiseSize::Pot(0.66),
+        RaiseSize::Pot(1.0),
+    ]);
 
     let trainer_config = TrainerConfig {
         learning_rate: 1e-5,
@@ -34,6 +35,7 @@ fn main() {
         use_entropy: false,
         entropy_beta: 0.01,
         agents_device: Device::Cpu,
+        seed: 0,
     };
 
     let device = Device::cuda_if_available(0).unwrap();
@@ -41,6 +43,23 @@ fn main() {
     let args: Vec<String> = env::args().collect();
     let output = &args[1];
 
+    // "check [hand_count]" plays random hands with every game rule checked instead of training
+    if output == "check" {
+        let hand_count = match args.get(2).map_or(Ok(1_000_000), |arg| arg.parse::<u64>()) {
+            Ok(hand_count) => hand_count,
+            Err(err) => {
+                println!("Error: invalid hand count: {}", err);
+                return;
+            }
+        };
+        let mut progress = |hands_checked| println!("{} hands checked", hands_checked);
+        match game::invariants::check_random_hands(hand_count, trainer_config.seed, &mut progress) {
+            Ok(()) => println!("{} hands checked without error", hand_count),
+            Err(err) => println!("Error: {}", err),
+        }
+        return;
+    }
+
     let mut trainer =
         model::trainer::Trainer::new(3, &action_config, &trainer_config, device, output);
     if let Err(err) = trainer.train() {
@@ -31,13 +31,13 @@ impl PokerNetwork {
 
         let siamese_network = SiameseNetwork::new(
             player_count,
-            3 + action_config.postflop_raise_sizes.len() as u32, // Each raise size + fold, call, check
-            player_count as usize * 3, // 3 actions max per player per street => TODO: prevent situations where we have more than 3 actions
+            action_config.get_action_count() as u32, // Each raise slot + fold, call, all-in
+            action_config.max_actions_per_street as usize,
+            action_config.game_variant.get_rank_count(), // Short-deck has fewer ranks
             vb.pp("siamese"),
         )?;
 
-        let actor_network =
-            ActorNetwork::new(vb.pp("actor"), 3 + action_config.postflop_raise_sizes.len())?;
+        let actor_network = ActorNetwork::new(vb.pp("actor"), action_config.get_action_count())?;
 
         let critic_network = CriticNetwork::new(vb.pp("critic"))?;
 
@@ -231,11 +231,12 @@ impl SiameseNetwork {
         player_count: u32,
         action_abstraction_count: u32,
         max_action_per_street_cnt: usize,
+        rank_count: usize,
         vb: VarBuilder,
     ) -> Result<SiameseNetwork, candle_core::Error> {
         let features_size = [48, 96];
 
-        let card_input_size = (13, 4);
+        let card_input_size = (rank_count, 4);
         let card_output_size = card_input_size.0 * card_input_size.1 * features_size[1];
 
         let action_input_size = (action_abstraction_count as usize, player_count as usize + 2);
@@ -6,11 +6,14 @@ use crate::agent::agent_pool::AgentPool;
 use crate::agent::tournament::Tournament;
 use crate::agent::Agent;
 use crate::game::action::ActionConfig;
+use crate::game::exploitability::get_exploitability;
 use crate::game::hand_state::HandState;
 use crate::game::tree::Tree;
 
 use candle_core::{Device, Tensor};
 use candle_nn::{Optimizer, ParamsAdamW};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
@@ -55,6 +58,8 @@ impl<'a> Trainer<'a> {
         let reward_gamma = 0.999;
         let log_epsilon = 1e-10;
 
+        self.action_config.validate()?;
+
         let mut trained_network = PokerNetwork::new(
             self.player_cnt,
             self.action_config.clone(),
@@ -137,6 +142,7 @@ impl<'a> Trainer<'a> {
             let mut hand_states = self.build_hand_states(
                 &trained_network,
                 Arc::clone(&agent_pool),
+                self.get_seed(iteration),
                 self.trainer_config.epsilon_greedy_factor
                     * self
                         .trainer_config
@@ -156,10 +162,10 @@ impl<'a> Trainer<'a> {
                 let mut min_rewards = Vec::new();
                 let mut max_rewards = Vec::new();
                 let mut gamma_rewards = Vec::new();
-                let reward_ratio = self.action_config.buy_in as f32 * (self.player_cnt - 1) as f32;
-
                 for hand_state in hand_states.iter_mut() {
                     indexes.push(step_cnt);
+                    // Rewards are normalized by the effective stacks of the hand
+                    let reward_ratio = hand_state.get_reward_ratio();
                     let hand_rewards: Vec<f32> = hand_state
                         .get_traverser_action_states()
                         .iter()
@@ -327,10 +333,11 @@ impl<'a> Trainer<'a> {
                         let grad_policy = gradients_policy.get_id(v.id());
                         let grad_value = gradients_value.get_id(v.id());
 
-                        if k.starts_with("siamese") && grad_policy.is_some() && grad_value.is_some()
+                        if let (true, Some(grad_policy), Some(grad_value)) =
+                            (k.starts_with("siamese"), grad_policy, grad_value)
                         {
-                            let grad_weighted = ((grad_policy.unwrap() * 0.5).unwrap()
-                                + (grad_value.unwrap() * 0.5).unwrap())
+                            let grad_weighted = ((grad_policy * 0.5).unwrap()
+                                + (grad_value * 0.5).unwrap())
                             .unwrap();
                             gradients_embedding.insert(v, grad_weighted);
                         }
@@ -344,12 +351,23 @@ impl<'a> Trainer<'a> {
 
             // self.test_clone(&trained_network, iteration as u32)?;
             if iteration % 10 == 0 {
-                Tree::print_first_actions(
-                    &trained_network.clone(),
-                    &self.trainer_config.agents_device.clone(),
-                    self.trainer_config.no_invalid_for_traverser,
-                    self.action_config,
-                )?;
+                if self.action_config.game_variant.is_toy_game() {
+                    // Toy games are small enough to measure how far from a Nash equilibrium we are
+                    let agent = AgentNetwork::new(trained_network.clone());
+                    let exploitability = get_exploitability(
+                        self.action_config,
+                        &agent,
+                        &self.trainer_config.agents_device,
+                    )?;
+                    println!("Exploitability: {:.4} chips per hand", exploitability);
+                } else {
+                    Tree::print_first_actions(
+                        &trained_network.clone(),
+                        &self.trainer_config.agents_device.clone(),
+                        self.trainer_config.no_invalid_for_traverser,
+                        self.action_config,
+                    )?;
+                }
             }
 
             // for _ in 0..10 {
@@ -365,7 +383,7 @@ impl<'a> Trainer<'a> {
                 && (iteration % self.trainer_config.save_interval as usize == 0/*|| iteration == 25*/)
             {
                 let net_file =
-                    Path::new(&self.output_path).join(&format!("poker_network_{}.pt", iteration));
+                    Path::new(&self.output_path).join(format!("poker_network_{}.pt", iteration));
                 trained_network.save_var_map(net_file.clone())?;
             }
 
@@ -373,11 +391,16 @@ impl<'a> Trainer<'a> {
                 && (iteration % self.trainer_config.new_agent_interval as usize == 0/*|| iteration == 25*/)
             {
                 let net_file =
-                    Path::new(&self.output_path).join(&format!("poker_network_{}.pt", iteration));
+                    Path::new(&self.output_path).join(format!("poker_network_{}.pt", iteration));
                 tournament.add_agent(net_file.to_str().unwrap().to_string(), iteration as u32)?;
-                self.refresh_agents(Arc::clone(&agent_pool), &mut tournament, false)?;
+                self.refresh_agents(
+                    Arc::clone(&agent_pool),
+                    &mut tournament,
+                    false,
+                    self.get_seed(iteration),
+                )?;
                 tournament.save_state(
-                    Path::new(&self.output_path).join(&format!("tournament_{}.txt", iteration)),
+                    Path::new(&self.output_path).join(format!("tournament_{}.txt", iteration)),
                 );
             }
         }
@@ -389,15 +412,17 @@ impl<'a> Trainer<'a> {
         &self,
         trained_network: &PokerNetwork,
         agent_pool: Arc<Mutex<AgentPool>>,
+        seed: u64,
         epsilon_greedy: f32,
     ) -> Result<Vec<HandState>, candle_core::Error> {
         let start_time = Instant::now();
-        let hand_states_base = Arc::new(Mutex::new(Vec::new()));
+        // One slot per worker, so hand states keep the same order whatever the thread timing
+        let hand_states_base = Arc::new(Mutex::new(vec![Vec::new(); self.n_workers]));
         let trained_agent_base: Arc<Box<dyn Agent>> =
             Arc::new(Box::new(AgentNetwork::new(trained_network.clone())));
 
         // Clone trained network for inference
-        for _ in 0..self.n_workers {
+        for worker in 0..self.n_workers {
             let hand_states = Arc::clone(&hand_states_base);
             let trained_agent = Arc::clone(&trained_agent_base);
             let agent_pool_clone = Arc::clone(&agent_pool);
@@ -410,16 +435,19 @@ impl<'a> Trainer<'a> {
 
             self.thread_pool.execute(move || {
                 let mut new_hand_states = Vec::new();
+                let mut rng = StdRng::seed_from_u64(seed.wrapping_add(worker as u64));
+                let mut tree = Tree::new(player_cnt, &action_config, rng.gen());
 
                 for _ in 0..iterations {
-                    let mut tree = Tree::new(player_cnt, &action_config);
+                    // Every traverser plays from each position as the button moves
+                    tree.move_button();
 
                     for traverser in 0..player_cnt {
                     // Select agents
                     let mut agents = Vec::new();
                     for p in 0..player_cnt {
                         let agent = if p != traverser {
-                            agent_pool_clone.lock().unwrap().get_agent().1
+                            agent_pool_clone.lock().unwrap().get_agent(&mut rng).1
                         } else {
                             Arc::clone(&trained_agent)
                         };
@@ -476,7 +504,7 @@ impl<'a> Trainer<'a> {
                     }
                 }
             }
-            hand_states.lock().unwrap().append(&mut new_hand_states);
+            hand_states.lock().unwrap()[worker].append(&mut new_hand_states);
         });
         }
 
@@ -488,7 +516,15 @@ impl<'a> Trainer<'a> {
         Ok(Arc::try_unwrap(hand_states_base)
             .unwrap()
             .into_inner()
-            .unwrap())
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    // Each iteration gets its own seed, so a resumed training replays the same hands
+    fn get_seed(&self, iteration: usize) -> u64 {
+        StdRng::seed_from_u64(self.trainer_config.seed ^ iteration as u64).gen()
     }
 
     fn get_action_indexes(&self, hand_states: &[HandState]) -> Result<Tensor, candle_core::Error> {
@@ -646,7 +682,12 @@ impl<'a> Trainer<'a> {
                 pool.set_agents(&best_agents);
             } else {
                 println!("Refreshing agents...");
-                self.refresh_agents(Arc::clone(&agent_pool), tournament, true)?;
+                self.refresh_agents(
+                    Arc::clone(&agent_pool),
+                    tournament,
+                    true,
+                    self.get_seed(latest_iteration as usize),
+                )?;
                 tournament.save_state(
                     trained_network_path
                         .join(format!("tournament_{}.txt", latest_tournament_index)),
@@ -662,6 +703,7 @@ impl<'a> Trainer<'a> {
         agent_pool: Arc<Mutex<AgentPool>>,
         tournament: &mut Tournament,
         init: bool,
+        seed: u64,
     ) -> Result<(), candle_core::Error> {
         if init {
             // List files in output path
@@ -711,7 +753,7 @@ impl<'a> Trainer<'a> {
             agent_pool.lock().unwrap().set_agents(&best_agents);
         } else {
             println!("Playing tournament...");
-            tournament.play(400000);
+            tournament.play(400000, seed);
             println!("Done...");
             let best_agents = tournament.get_best_agents(self.trainer_config.agent_count as usize);
             agent_pool.lock().unwrap().set_agents(&best_agents);
@@ -32,4 +32,6 @@ pub struct TrainerConfig {
     pub entropy_beta: f64,
     // Device used for agents in rollout and tournament
     pub agents_device: Device,
+    // Seed for dealing and agent sampling, the same seed reproduces the same rollouts
+    pub seed: u64,
 }